@@ -0,0 +1,96 @@
+use crate::prelude::*;
+use std::time::{Duration, Instant};
+
+/// The approach the pathfinding cache replaced: one Dijkstra map for every walkable tile,
+/// built up front for each level.
+fn precompute_all_dijkstra_maps(map: &Map) -> Vec<Option<DijkstraMap>> {
+    map.tiles
+        .iter()
+        .enumerate()
+        .map(|(idx, _)| {
+            if map.can_enter_tile(map.map_point(idx)) {
+                Some(DijkstraMap::new(
+                    map.width,
+                    map.height,
+                    &[idx],
+                    map,
+                    PATHFINDING_MAX_DEPTH,
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn megabytes(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+fn milliseconds(duration: Duration, levels: usize) -> f64 {
+    duration.as_secs_f64() * 1000.0 / levels as f64
+}
+
+/// Generates the given number of levels and compares building every Dijkstra map up front with
+/// filling the pathfinding cache to capacity on demand.
+pub fn run_benchmark(
+    architects: &ArchitectRegistry,
+    vaults: &VaultLibrary,
+    seed: u64,
+    levels: usize,
+) -> Result<(), String> {
+    let mut rng = RandomNumberGenerator::seeded(seed);
+    let mut generation = Duration::ZERO;
+    let mut precompute = Duration::ZERO;
+    let mut on_demand = Duration::ZERO;
+    let mut precompute_bytes = 0;
+    let mut on_demand_bytes = 0;
+
+    for level in 0..levels {
+        let start = Instant::now();
+        let map_builder = MapBuilder::new(&mut rng, architects, vaults, level % NUM_LEVELS)?;
+        generation += start.elapsed();
+
+        let start = Instant::now();
+        let dijkstra_maps = precompute_all_dijkstra_maps(&map_builder.map);
+        precompute += start.elapsed();
+        precompute_bytes += dijkstra_maps
+            .iter()
+            .flatten()
+            .map(|dijkstra_map| dijkstra_map.map.len() * std::mem::size_of::<f32>())
+            .sum::<usize>();
+
+        let walkable: Vec<usize> = (0..map_builder.map.tiles.len())
+            .filter(|idx| {
+                map_builder
+                    .map
+                    .can_enter_tile(map_builder.map.map_point(*idx))
+            })
+            .collect();
+        let start = Instant::now();
+        let mut cache = PathfindingCache::new(PATHFINDING_CACHE_SIZE);
+        for _ in 0..PATHFINDING_CACHE_SIZE {
+            let target = *rng.random_slice_entry(&walkable).unwrap();
+            cache.dijkstra_map(&map_builder.map, target, false);
+        }
+        on_demand += start.elapsed();
+        on_demand_bytes += cache.memory_usage();
+    }
+
+    println!("Levels generated:            {}", levels);
+    println!(
+        "Level generation:            {:.2} ms per level",
+        milliseconds(generation, levels)
+    );
+    println!(
+        "Precomputed Dijkstra maps:   {:.2} ms, {:.2} MB per level",
+        milliseconds(precompute, levels),
+        megabytes(precompute_bytes / levels)
+    );
+    println!(
+        "Full pathfinding cache:      {:.2} ms, {:.2} MB per level",
+        milliseconds(on_demand, levels),
+        megabytes(on_demand_bytes / levels)
+    );
+    Ok(())
+}
@@ -5,24 +5,45 @@ pub struct Camera {
     pub right_x: i32,
     pub top_y: i32,
     pub bottom_y: i32,
+    map_width: i32,
+    map_height: i32,
 }
 
 impl Camera {
-    pub fn new(player_position: Point) -> Self {
+    pub fn new(player_position: Point, map: &Map) -> Self {
         let mut result = Self {
             left_x: 0,
             right_x: 0,
             top_y: 0,
             bottom_y: 0,
+            map_width: map.width,
+            map_height: map.height,
         };
         result.on_player_move(player_position);
         result
     }
 
     pub fn on_player_move(&mut self, player_position: Point) {
-        self.left_x = player_position.x - DISPLAY_WIDTH / 2;
+        self.left_x = Self::clamp_to_map(
+            player_position.x - DISPLAY_WIDTH / 2,
+            DISPLAY_WIDTH,
+            self.map_width,
+        );
         self.right_x = self.left_x + DISPLAY_WIDTH;
-        self.top_y = player_position.y - DISPLAY_HEIGHT / 2;
+        self.top_y = Self::clamp_to_map(
+            player_position.y - DISPLAY_HEIGHT / 2,
+            DISPLAY_HEIGHT,
+            self.map_height,
+        );
         self.bottom_y = self.top_y + DISPLAY_HEIGHT;
     }
+
+    /// Keeps the view inside the map, or centres the map when it is smaller than the view.
+    fn clamp_to_map(start: i32, view_size: i32, map_size: i32) -> i32 {
+        if map_size <= view_size {
+            (map_size - view_size) / 2
+        } else {
+            start.clamp(0, map_size - view_size)
+        }
+    }
 }
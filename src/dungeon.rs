@@ -0,0 +1,30 @@
+use crate::prelude::*;
+use std::collections::BTreeMap;
+
+/// A level the player has left: its map, theme and every entity still on it, kept in a world
+/// of its own so the systems only ever see the level the player is on.
+pub struct StoredLevel {
+    pub map: Map,
+    pub theme: ThemeKind,
+    pub ecs: World,
+}
+
+/// Every level the player has visited apart from the current one, keyed by depth.
+#[derive(Default)]
+pub struct Dungeon {
+    levels: BTreeMap<u32, StoredLevel>,
+}
+
+impl Dungeon {
+    pub fn store(&mut self, depth: u32, level: StoredLevel) {
+        self.levels.insert(depth, level);
+    }
+
+    pub fn take(&mut self, depth: u32) -> Option<StoredLevel> {
+        self.levels.remove(&depth)
+    }
+
+    pub fn levels(&self) -> impl Iterator<Item = (u32, &StoredLevel)> {
+        self.levels.iter().map(|(depth, level)| (*depth, level))
+    }
+}
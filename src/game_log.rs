@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+const LOG_CAPACITY: usize = 200;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameLog {
+    entries: VecDeque<String>,
+    #[serde(skip)]
+    scroll: usize,
+}
+
+impl GameLog {
+    pub fn add<S: Into<String>>(&mut self, entry: S) {
+        if self.entries.len() >= LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry.into());
+        self.scroll = 0;
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = usize::min(self.scroll + lines, self.entries.len().saturating_sub(1));
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    pub fn is_scrolled(&self) -> bool {
+        self.scroll > 0
+    }
+
+    /// The newest `lines` entries before the scroll position, oldest first.
+    pub fn visible(&self, lines: usize) -> impl Iterator<Item = &String> {
+        let end = self.entries.len() - self.scroll;
+        let start = end.saturating_sub(lines);
+        self.entries.range(start..end)
+    }
+}
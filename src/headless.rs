@@ -0,0 +1,281 @@
+use crate::prelude::*;
+use crate::State;
+
+const MOVES: [Direction; 4] = [
+    Direction::West,
+    Direction::East,
+    Direction::North,
+    Direction::South,
+];
+
+const DIAGONAL_MOVES: [Direction; 4] = [
+    Direction::NorthWest,
+    Direction::NorthEast,
+    Direction::SouthWest,
+    Direction::SouthEast,
+];
+
+#[derive(Default)]
+struct Report {
+    turns: usize,
+    games: usize,
+    deaths: usize,
+    victories: usize,
+    deepest_level: u32,
+}
+
+/// Plays the game in place of a human: picks up whatever it stands on, drinks healing when
+/// hurt or rests if no enemy is in view, fights adjacent enemies and otherwise heads for the
+/// exit with the odd random step.
+struct Autopilot {
+    rng: RandomNumberGenerator,
+}
+
+impl Autopilot {
+    fn next_action(&mut self, ecs: &World, map: &Map) -> Action {
+        let (player_entity, player_pos, health) = <(Entity, &Point, &Health)>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .map(|(entity, pos, health)| (*entity, *pos, *health))
+            .next()
+            .unwrap();
+
+        let standing_on_item = <&Point>::query()
+            .filter(component::<Item>())
+            .iter(ecs)
+            .any(|pos| *pos == player_pos);
+        if standing_on_item {
+            return Action::Pickup;
+        }
+
+        if health.current * 2 < health.max {
+            let healing = carried_items(ecs, player_entity)
+                .iter()
+                .position(|(item, _, _)| {
+                    ecs.entry_ref(*item)
+                        .unwrap()
+                        .get_component::<ProvidesHealing>()
+                        .is_ok()
+                });
+            if let Some(slot) = healing {
+                return Action::UseSlot(slot + 1);
+            }
+
+            let fov = <&FieldOfView>::query()
+                .filter(component::<Player>())
+                .iter(ecs)
+                .next()
+                .unwrap();
+            let enemy_in_view = <&Point>::query()
+                .filter(component::<Enemy>())
+                .iter(ecs)
+                .any(|pos| fov.visible_tiles.contains(pos));
+            if !enemy_in_view {
+                return Action::Rest;
+            }
+        }
+
+        let moves: Vec<Direction> = if map.diagonal_movement {
+            MOVES.iter().chain(DIAGONAL_MOVES.iter()).copied().collect()
+        } else {
+            MOVES.to_vec()
+        };
+
+        let adjacent_enemy = <&Point>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .find_map(|pos| {
+                moves
+                    .iter()
+                    .find(|direction| player_pos + direction.delta() == *pos)
+            });
+        if let Some(direction) = adjacent_enemy {
+            return Action::Move(*direction);
+        }
+
+        let target = <&Point>::query()
+            .filter(component::<AmuletOfYala>())
+            .iter(ecs)
+            .next()
+            .copied()
+            .or_else(|| map.position_of(TileType::Exit));
+        if let (Some(target), false) = (target, self.rng.range(0, 5) == 0) {
+            let door_opener_map = map.for_door_openers();
+            let dijkstra_map = DijkstraMap::new(
+                map.width,
+                map.height,
+                &[map.map_idx(target)],
+                &door_opener_map,
+                1024.0,
+            );
+            let next_step = door_opener_map
+                .get_available_exits(map.map_idx(player_pos))
+                .iter()
+                .min_by(|a, b| {
+                    dijkstra_map.map[a.0]
+                        .partial_cmp(&dijkstra_map.map[b.0])
+                        .unwrap()
+                })
+                .map(|(idx, _)| map.map_point(*idx) - player_pos);
+            if let Some(direction) = moves
+                .iter()
+                .find(|direction| Some(direction.delta()) == next_step)
+            {
+                return Action::Move(*direction);
+            }
+        }
+
+        Action::Move(moves[self.rng.range(0, moves.len())])
+    }
+}
+
+fn check_invariants(ecs: &World, map: &Map, dungeon: &Dungeon) -> Result<(), String> {
+    let players = <&Player>::query().iter(ecs).count();
+    if players != 1 {
+        return Err(format!("Expected exactly one player, found {}", players));
+    }
+
+    for (entity, pos) in <(Entity, &Point)>::query().iter(ecs) {
+        if !map.in_bounds(*pos) || !map.can_enter_tile(*pos) {
+            return Err(format!(
+                "{:?} stands on a blocked tile at {:?}",
+                entity, pos
+            ));
+        }
+    }
+
+    for (entity, health) in <(Entity, &Health)>::query().iter(ecs) {
+        if health.current > health.max {
+            return Err(format!(
+                "{:?} has {} of {} hp",
+                entity, health.current, health.max
+            ));
+        }
+    }
+
+    let held_on_map = <(Entity, &Point)>::query()
+        .filter(component::<Carried>() | component::<Equipped>())
+        .iter(ecs)
+        .map(|(entity, _)| *entity)
+        .next();
+    if let Some(entity) = held_on_map {
+        return Err(format!("{:?} is held but still placed on the map", entity));
+    }
+
+    for (depth, level) in dungeon.levels() {
+        if <&Player>::query().iter(&level.ecs).next().is_some() {
+            return Err(format!("Stored level {} contains the player", depth + 1));
+        }
+        for (entity, pos) in <(Entity, &Point)>::query().iter(&level.ecs) {
+            if !level.map.can_enter_tile(*pos) {
+                return Err(format!(
+                    "{:?} stands on a blocked tile at {:?} on stored level {}",
+                    entity,
+                    pos,
+                    depth + 1
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl State {
+    fn headless(
+        templates: Templates,
+        architects: ArchitectRegistry,
+        vaults: VaultLibrary,
+        keymap: Keymap,
+        seed: u64,
+        diagonal_movement: bool,
+    ) -> Result<Self, String> {
+        let mut state = Self::empty(
+            templates,
+            architects,
+            vaults,
+            keymap,
+            seed,
+            diagonal_movement,
+            false,
+        );
+        state.reset_game_state(seed)?;
+        Ok(state)
+    }
+}
+
+/// Runs the logic schedules without a window for the given number of player turns, starting a
+/// new game whenever one ends. Fails on the first broken invariant, naming the game's seed.
+pub fn run_headless(
+    templates: Templates,
+    architects: ArchitectRegistry,
+    vaults: VaultLibrary,
+    keymap: Keymap,
+    seed: u64,
+    turns: usize,
+    diagonal_movement: bool,
+) -> Result<(), String> {
+    let mut state = State::headless(
+        templates,
+        architects,
+        vaults,
+        keymap,
+        seed,
+        diagonal_movement,
+    )?;
+    let mut autopilot = Autopilot {
+        rng: RandomNumberGenerator::seeded(seed),
+    };
+    let mut report = Report::default();
+
+    while report.turns < turns {
+        let current_state = *state.resources.get::<TurnState>().unwrap();
+        match current_state {
+            TurnState::AwaitingInput => {
+                let resting = <&Resting>::query().iter(&state.ecs).next().is_some();
+                let action = if resting {
+                    None
+                } else {
+                    Some(autopilot.next_action(&state.ecs, &state.resources.get::<Map>().unwrap()))
+                };
+                state.resources.insert(action);
+                state.resources.insert(Point::zero());
+                state.resources.insert(MouseClick(None));
+                report.turns += 1;
+            }
+            TurnState::GameOver | TurnState::Victory => {
+                report.games += 1;
+                if current_state == TurnState::GameOver {
+                    report.deaths += 1;
+                } else {
+                    report.victories += 1;
+                }
+                let next_seed = autopilot.rng.next_u64();
+                state.reset_game_state(next_seed)?;
+                continue;
+            }
+            _ => {}
+        }
+
+        state
+            .execute(current_state)
+            .map_err(|err| format!("Seed {}, turn {}: {}", state.seed, report.turns, err))?;
+
+        <&Player>::query()
+            .iter(&state.ecs)
+            .for_each(|player| report.deepest_level = report.deepest_level.max(player.map_level));
+        check_invariants(
+            &state.ecs,
+            &state.resources.get::<Map>().unwrap(),
+            &state.resources.get::<Dungeon>().unwrap(),
+        )
+        .map_err(|err| format!("Seed {}, turn {}: {}", state.seed, report.turns, err))?;
+    }
+
+    println!("Turns played:     {}", report.turns);
+    println!("Games finished:   {}", report.games);
+    println!("Deaths:           {}", report.deaths);
+    println!("Victories:        {}", report.victories);
+    println!("Deepest level:    {}", report.deepest_level + 1);
+    Ok(())
+}
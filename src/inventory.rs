@@ -0,0 +1,100 @@
+use crate::prelude::*;
+
+/// Whether the inventory screen is open, and which of the listed items is selected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InventoryScreen {
+    Closed,
+    Open { selected: usize },
+}
+
+/// Every item carried or equipped by `owner`, carried items first, each group by name.
+pub fn inventory_items<T: EntityStore>(ecs: &T, owner: Entity) -> Vec<(Entity, String, bool)> {
+    let mut items: Vec<(Entity, String, bool)> = <(Entity, &Name, &Carried)>::query()
+        .filter(component::<Item>())
+        .iter(ecs)
+        .filter(|(_, _, carried)| carried.by == owner)
+        .map(|(entity, name, _)| (*entity, name.name.clone(), false))
+        .chain(
+            <(Entity, &Name, &Equipped)>::query()
+                .filter(component::<Item>())
+                .iter(ecs)
+                .filter(|(_, _, equipped)| equipped.by == owner)
+                .map(|(entity, name, _)| (*entity, name.name.clone(), true)),
+        )
+        .collect();
+    items.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.1.cmp(&b.1)));
+    items
+}
+
+/// The items `owner` carries without having them equipped, in the order the number keys use them.
+pub fn carried_items<T: EntityStore>(ecs: &T, owner: Entity) -> Vec<(Entity, String, bool)> {
+    inventory_items(ecs, owner)
+        .into_iter()
+        .filter(|(_, _, equipped)| !equipped)
+        .collect()
+}
+
+/// One line for each effect the item provides, for the inspect pane.
+pub fn describe_item(ecs: &SubWorld, item: Entity) -> Vec<String> {
+    let item = ecs.entry_ref(item).unwrap();
+    let mut lines = Vec::new();
+    if let Ok(healing) = item.get_component::<ProvidesHealing>() {
+        lines.push(format!("Heals {} hit points.", healing.amount));
+    }
+    if item.get_component::<ProvidesDungeonMap>().is_ok() {
+        lines.push("Reveals the map of the level.".to_string());
+    }
+    if let Ok(equippable) = item.get_component::<Equippable>() {
+        lines.push(format!("Equipped in the {}.", equippable.slot.name()));
+    }
+    if let Ok(damage) = item.get_component::<Damage>() {
+        lines.push(format!("Damage: +{}", damage.damage));
+    }
+    if let Ok(armor) = item.get_component::<Armor>() {
+        lines.push(format!("Armor: {}", armor.armor));
+    }
+    if let Ok(ranged) = item.get_component::<Ranged>() {
+        lines.push(format!("Range: {}", ranged.range));
+    }
+    if item.get_component::<ProvidesThrowing>().is_ok() {
+        lines.push("Can be thrown.".to_string());
+    }
+    if item.get_component::<ProvidesDepletion>().is_ok() {
+        lines.push("Used up when used.".to_string());
+    }
+    if item
+        .get_component::<ProvidesDestructionOnLevelProgress>()
+        .is_ok()
+    {
+        lines.push("Crumbles when you leave the level.".to_string());
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_keys_skip_equipped_items() {
+        let mut ecs = World::default();
+        let player = ecs.push((Player { map_level: 0 },));
+        let named = |name: &str| Name {
+            name: name.to_string(),
+        };
+        let sword = ecs.push((Item, named("Shiny Sword"), Equipped { by: player }));
+        let potion = ecs.push((Item, named("Healing Potion"), Carried { by: player }));
+        let map = ecs.push((Item, named("Dungeon Map"), Carried { by: player }));
+
+        let listed: Vec<Entity> = inventory_items(&ecs, player)
+            .into_iter()
+            .map(|(item, _, _)| item)
+            .collect();
+        assert_eq!(listed, vec![map, potion, sword]);
+        let numbered: Vec<Entity> = carried_items(&ecs, player)
+            .into_iter()
+            .map(|(item, _, _)| item)
+            .collect();
+        assert_eq!(numbered, vec![map, potion]);
+    }
+}
@@ -0,0 +1,299 @@
+use crate::prelude::*;
+use serde::Deserialize;
+
+const KEYMAP_FILE: &str = "resources/keymap.ron";
+
+macro_rules! keys {
+    ($($key:ident),* $(,)?) => {
+        /// The keys a keymap can bind, named as in `VirtualKeyCode`.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+        pub enum Key {
+            $($key),*
+        }
+
+        impl Key {
+            pub fn code(self) -> VirtualKeyCode {
+                match self {
+                    $(Key::$key => VirtualKeyCode::$key),*
+                }
+            }
+        }
+    };
+}
+
+keys!(
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Key0, Key1, Key2,
+    Key3, Key4, Key5, Key6, Key7, Key8, Key9, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5,
+    Numpad6, Numpad7, Numpad8, Numpad9, Left, Right, Up, Down, PageUp, PageDown, Home, End, Escape,
+    Return, Space, Tab, Back, Period, Comma, Slash, Semicolon, Minus, Equals,
+);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Direction {
+    North,
+    South,
+    West,
+    East,
+    NorthWest,
+    NorthEast,
+    SouthWest,
+    SouthEast,
+}
+
+impl Direction {
+    pub fn delta(self) -> Point {
+        match self {
+            Direction::North => Point::new(0, -1),
+            Direction::South => Point::new(0, 1),
+            Direction::West => Point::new(-1, 0),
+            Direction::East => Point::new(1, 0),
+            Direction::NorthWest => Point::new(-1, -1),
+            Direction::NorthEast => Point::new(1, -1),
+            Direction::SouthWest => Point::new(-1, 1),
+            Direction::SouthEast => Point::new(1, 1),
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        let delta = self.delta();
+        delta.x != 0 && delta.y != 0
+    }
+}
+
+/// Something the player asks for with a key press.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Action {
+    Move(Direction),
+    Pickup,
+    /// Uses the carried item listed with this number, counting from 1.
+    UseSlot(usize),
+    Fire,
+    Throw,
+    Search,
+    /// Closes the open doors next to the player.
+    CloseDoor,
+    /// Lets a turn pass.
+    Wait,
+    /// Waits until healed or an enemy comes into view.
+    Rest,
+    /// Opens or closes the inventory screen.
+    Inventory,
+    /// Drops the item selected on the inventory screen.
+    Drop,
+    /// Fires at the nearest enemy while aiming, or uses the item selected on the inventory
+    /// screen.
+    Confirm,
+    /// Stops aiming or closes the inventory screen.
+    Cancel,
+    ScrollLogUp,
+    ScrollLogDown,
+    Save,
+}
+
+/// The key shown for a binding, such as 1 for `Key1`.
+fn key_name(key: Key) -> String {
+    let name = format!("{:?}", key);
+    match name.strip_prefix("Key") {
+        Some(digit) if digit.len() == 1 => digit.to_string(),
+        _ => name,
+    }
+}
+
+/// Which action each key asks for, read from `resources/keymap.ron`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Keymap {
+    pub fn load() -> Result<Self, String> {
+        let source = std::fs::read_to_string(KEYMAP_FILE)
+            .map_err(|err| format!("Unable to read {}: {}", KEYMAP_FILE, err))?;
+        let keymap: Self = ron::de::from_str(&source)
+            .map_err(|err| format!("{}:{}: {}", KEYMAP_FILE, err.position, err.code))?;
+        let problems = keymap.validate();
+        if problems.is_empty() {
+            Ok(keymap)
+        } else {
+            Err(format!("{}: {}", KEYMAP_FILE, problems.join("; ")))
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (idx, (key, action)) in self.bindings.iter().enumerate() {
+            if self.bindings[..idx].iter().any(|(other, _)| other == key) {
+                problems.push(format!("[{:?}] Key is bound more than once", key));
+            }
+            if *action == Action::UseSlot(0) {
+                problems.push(format!("[{:?}] Slots are numbered from 1", key));
+            }
+        }
+        if self.key_for(Action::Cancel).is_none() {
+            problems.push("No key to cancel aiming".to_string());
+        }
+        problems
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound.code() == key)
+            .map(|(_, action)| *action)
+    }
+
+    fn key_for(&self, action: Action) -> Option<Key> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+    }
+
+    /// Two lines of help naming the first key bound to each action the player can take: how to
+    /// move, then everything else.
+    pub fn help(&self, diagonal_movement: bool) -> [String; 2] {
+        let mut movement = vec!["Explore the Dungeon.".to_string()];
+        let mut help = Vec::new();
+
+        let moves: Vec<String> = [
+            Direction::North,
+            Direction::South,
+            Direction::West,
+            Direction::East,
+            Direction::NorthWest,
+            Direction::NorthEast,
+            Direction::SouthWest,
+            Direction::SouthEast,
+        ]
+        .into_iter()
+        .filter(|direction| diagonal_movement || !direction.is_diagonal())
+        .filter_map(|direction| self.key_for(Action::Move(direction)))
+        .map(key_name)
+        .collect();
+        if !moves.is_empty() {
+            movement.push(format!("{} to move.", moves.join("/")));
+        }
+
+        let slots: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, action)| matches!(action, Action::UseSlot(_)))
+            .map(|(key, _)| key_name(*key))
+            .collect();
+        match slots.as_slice() {
+            [] => {}
+            [slot] => help.push(format!("{} to use.", slot)),
+            [first, .., last] => help.push(format!("{}-{} to use.", first, last)),
+        }
+
+        [
+            (Action::Pickup, "pick up"),
+            (Action::Fire, "fire"),
+            (Action::Throw, "throw"),
+            (Action::Search, "search"),
+            (Action::CloseDoor, "close a door"),
+            (Action::Wait, "wait"),
+            (Action::Rest, "rest"),
+            (Action::Inventory, "open the inventory"),
+            (Action::Save, "save and quit"),
+        ]
+        .into_iter()
+        .filter_map(|(action, what)| {
+            self.key_for(action)
+                .map(|key| format!("{} to {}.", key_name(key), what))
+        })
+        .for_each(|line| help.push(line));
+
+        [movement.join(" "), help.join(" ")]
+    }
+
+    /// A line of help for the inventory screen, naming the first key bound to each action.
+    pub fn inventory_help(&self) -> String {
+        let mut help = Vec::new();
+        let select: Vec<String> = [Direction::North, Direction::South]
+            .into_iter()
+            .filter_map(|direction| self.key_for(Action::Move(direction)))
+            .map(key_name)
+            .collect();
+        if !select.is_empty() {
+            help.push(format!("{} to select.", select.join("/")));
+        }
+        [
+            (Action::Confirm, "use, equip or take off"),
+            (Action::Drop, "drop"),
+            (Action::Cancel, "close"),
+        ]
+        .into_iter()
+        .filter_map(|(action, what)| {
+            self.key_for(action)
+                .map(|key| format!("{} to {}.", key_name(key), what))
+        })
+        .for_each(|line| help.push(line));
+        help.join(" ")
+    }
+
+    /// The title of the message log, naming the keys that scroll it.
+    pub fn log_title(&self, scrolled: bool) -> String {
+        let mut notes = Vec::new();
+        let scroll: Vec<String> = [Action::ScrollLogUp, Action::ScrollLogDown]
+            .into_iter()
+            .filter_map(|action| self.key_for(action))
+            .map(key_name)
+            .collect();
+        if !scroll.is_empty() {
+            notes.push(scroll.join("/"));
+        }
+        if scrolled {
+            notes.push("scrolled".to_string());
+        }
+        if notes.is_empty() {
+            "Log".to_string()
+        } else {
+            format!("Log ({})", notes.join(", "))
+        }
+    }
+
+    /// What the player can do while aiming, naming the first key bound to each action.
+    pub fn aiming_help(&self) -> String {
+        let mut help = vec!["Click a target".to_string()];
+        if let Some(key) = self
+            .key_for(Action::Fire)
+            .or_else(|| self.key_for(Action::Confirm))
+        {
+            help.push(format!("{} for the nearest enemy", key_name(key)));
+        }
+        if let Some(key) = self.key_for(Action::Cancel) {
+            help.push(format!("{} to cancel", key_name(key)));
+        }
+        format!("{}.", help.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_and_aiming_help_follow_the_bindings() {
+        let keymap = Keymap {
+            bindings: vec![
+                (Key::Home, Action::ScrollLogUp),
+                (Key::End, Action::ScrollLogDown),
+                (Key::Return, Action::Confirm),
+                (Key::Q, Action::Cancel),
+            ],
+        };
+        assert_eq!(keymap.log_title(false), "Log (Home/End)");
+        assert_eq!(keymap.log_title(true), "Log (Home/End, scrolled)");
+        assert_eq!(
+            keymap.aiming_help(),
+            "Click a target, Return for the nearest enemy, Q to cancel."
+        );
+
+        let unbound = Keymap {
+            bindings: Vec::new(),
+        };
+        assert_eq!(unbound.log_title(true), "Log (scrolled)");
+        assert_eq!(unbound.aiming_help(), "Click a target.");
+    }
+}
@@ -1,9 +1,19 @@
+mod benchmark;
 mod camera;
 mod components;
+mod dungeon;
+mod game_log;
+mod headless;
+mod inventory;
+mod keymap;
 mod map;
 mod map_builder;
+mod options;
+mod pathfinding;
+mod save;
 mod spawner;
 mod systems;
+mod targeting;
 mod tint_scaling;
 mod turn_state;
 
@@ -14,16 +24,29 @@ mod prelude {
     pub use legion::*;
     pub const SCREEN_WIDTH: i32 = 80;
     pub const SCREEN_HEIGHT: i32 = 50;
+    /// How deep the dungeon goes; the amulet lies on the last level. This is a fixed part of the
+    /// game's design rather than an option: the levels in `resources/template.ron`, the minimum
+    /// depths in `resources/vaults.ron` and the weights in `resources/architects.ron` are all
+    /// checked against it, so a deeper dungeon needs those files extended as well.
+    pub const NUM_LEVELS: usize = 3;
     pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
     pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
     pub const FOREGROUND_MIN: f32 = 0.25;
     pub const FOREGROUND_FROM_MAP: f32 = 0.15;
     pub use crate::camera::*;
     pub use crate::components::*;
+    pub use crate::dungeon::*;
+    pub use crate::game_log::*;
+    pub use crate::inventory::*;
+    pub use crate::keymap::*;
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::options::*;
+    pub use crate::pathfinding::*;
+    pub use crate::save::*;
     pub use crate::spawner::*;
     pub use crate::systems::*;
+    pub use crate::targeting::*;
     pub use crate::tint_scaling::*;
     pub use crate::turn_state::*;
 }
@@ -36,61 +59,110 @@ struct State {
     input_systems: Schedule,
     player_systems: Schedule,
     monster_systems: Schedule,
+    templates: Templates,
+    architects: ArchitectRegistry,
+    vaults: VaultLibrary,
+    keymap: Keymap,
+    seed: u64,
+    diagonal_movement: bool,
 }
 
 impl State {
-    fn new() -> Self {
-        let mut ecs = World::default();
-        let mut resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut ecs, map_builder.player_start);
-        let mut map = map_builder.map;
-        let exit_idx = Map::map_idx(map_builder.amulet_start);
-        map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(
-            &mut ecs,
-            &mut resources,
-            &mut rng,
-            0,
-            &map_builder.entity_spawns,
-        );
-        resources.insert(map);
-        resources.insert(Camera::new(map_builder.player_start));
-        resources.insert(TurnState::AwaitingInput);
-        resources.insert(map_builder.theme);
+    fn empty(
+        templates: Templates,
+        architects: ArchitectRegistry,
+        vaults: VaultLibrary,
+        keymap: Keymap,
+        seed: u64,
+        diagonal_movement: bool,
+        rendering: bool,
+    ) -> Self {
         Self {
-            ecs,
-            resources,
-            input_systems: build_input_scheduler(),
-            player_systems: build_player_scheduler(),
-            monster_systems: build_monster_scheduler(),
+            ecs: World::default(),
+            resources: Resources::default(),
+            input_systems: build_input_scheduler(rendering),
+            player_systems: build_player_scheduler(rendering),
+            monster_systems: build_monster_scheduler(rendering),
+            templates,
+            architects,
+            vaults,
+            keymap,
+            seed,
+            diagonal_movement,
         }
     }
 
-    fn reset_game_state(&mut self) {
+    fn new(
+        templates: Templates,
+        architects: ArchitectRegistry,
+        vaults: VaultLibrary,
+        keymap: Keymap,
+        seed: u64,
+        diagonal_movement: bool,
+    ) -> Result<Self, String> {
+        let mut state = Self::empty(
+            templates,
+            architects,
+            vaults,
+            keymap,
+            seed,
+            diagonal_movement,
+            true,
+        );
+        state.reset_game_state(seed)?;
+        Ok(state)
+    }
+
+    fn load(
+        templates: Templates,
+        architects: ArchitectRegistry,
+        vaults: VaultLibrary,
+        keymap: Keymap,
+        save_game: SaveGame,
+    ) -> Self {
+        let mut state = Self::empty(
+            templates,
+            architects,
+            vaults,
+            keymap,
+            save_game.seed,
+            false,
+            true,
+        );
+        save_game.restore(&mut state.ecs, &mut state.resources);
+        state.resources.insert(state.keymap.clone());
+        state.diagonal_movement = state.resources.get::<Map>().unwrap().diagonal_movement;
+        state
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        SaveGame::capture(&self.ecs, &self.resources, self.seed).write()
+    }
+
+    fn reset_game_state(&mut self, seed: u64) -> Result<(), String> {
         self.ecs = World::default();
         self.resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut self.ecs, map_builder.player_start);
-        let exit_idx = Map::map_idx(map_builder.amulet_start);
-        let mut map = map_builder.map;
-        map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(
-            &mut self.ecs,
-            &mut self.resources,
-            &mut rng,
-            0,
-            &map_builder.entity_spawns,
-        );
+        self.seed = seed;
+        self.resources.insert(RandomNumberGenerator::seeded(seed));
+        self.resources.insert(Dungeon::default());
+        let (map, theme, player_start) = self.generate_level(0)?;
+        spawn_player(&mut self.ecs, player_start);
+        self.resources.insert(Camera::new(player_start, &map));
         self.resources.insert(map);
-        self.resources.insert(Camera::new(map_builder.player_start));
+        self.resources
+            .insert(PathfindingCache::new(PATHFINDING_CACHE_SIZE));
         self.resources.insert(TurnState::AwaitingInput);
-        self.resources.insert(map_builder.theme);
+        self.resources.insert(theme);
+        self.resources.insert(Targeting::Inactive);
+        self.resources.insert(InventoryScreen::Closed);
+        self.resources.insert(self.keymap.clone());
+        let mut log = GameLog::default();
+        log.add("You enter the dungeon in search of the Amulet of Yala.");
+        self.resources.insert(log);
+        Ok(())
     }
 
-    fn game_over(&mut self, ctx: &mut BTerm) {
+    fn game_over(&mut self, ctx: &mut BTerm) -> Result<(), String> {
         ctx.set_active_console(2);
         ctx.print_color_centered(2, RED, BLACK, "Your quest has ended.");
         ctx.print_color_centered(
@@ -112,13 +184,15 @@ impl State {
             "Don't worry, you can always try again with a new hero.",
         );
         ctx.print_color_centered(9, GREEN, BLACK, "Press 1 to play again.");
+        ctx.print_color_centered(11, GRAY, BLACK, format!("Seed: {}", self.seed));
 
         if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+            return self.reset_game_state(random_seed());
         }
+        Ok(())
     }
 
-    fn victory(&mut self, ctx: &mut BTerm) {
+    fn victory(&mut self, ctx: &mut BTerm) -> Result<(), String> {
         ctx.set_active_console(2);
         ctx.print_color_centered(2, GREEN, BLACK, "You have won!");
         ctx.print_color_centered(
@@ -134,95 +208,114 @@ impl State {
             "Your town is saved, and you can return to your normal life.",
         );
         ctx.print_color_centered(7, GREEN, BLACK, "Press 1 to play again.");
+        ctx.print_color_centered(9, GRAY, BLACK, format!("Seed: {}", self.seed));
 
         if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
+            return self.reset_game_state(random_seed());
         }
+        Ok(())
     }
 
-    fn advance_level(&mut self) {
-        let mut entities_to_keep = std::collections::HashSet::new();
-
+    fn change_level(&mut self, delta: i32) -> Result<(), String> {
         let player_entity = *<Entity>::query()
             .filter(component::<Player>())
             .iter(&self.ecs)
             .next()
             .unwrap();
 
-        entities_to_keep.insert(player_entity);
-
-        <(Entity, &Carried)>::query()
-            .filter(!component::<ProvidesDestructionOnLevelProgress>())
+        let destroyed: Vec<Entity> = <(Entity, &Carried)>::query()
+            .filter(component::<ProvidesDestructionOnLevelProgress>())
             .iter(&self.ecs)
             .filter(|(_, carry)| carry.by == player_entity)
-            .map(|(entity, _)| entity)
-            .for_each(|entity| {
-                entities_to_keep.insert(*entity);
-            });
+            .map(|(entity, _)| *entity)
+            .collect();
+        destroyed.into_iter().for_each(|entity| {
+            self.ecs.remove(entity);
+        });
 
-        <(Entity, &Equipped)>::query()
-            .filter(!component::<ProvidesDestructionOnLevelProgress>())
-            .iter(&self.ecs)
-            .filter(|(_, equipped)| equipped.by == player_entity)
-            .map(|(entity, _)| entity)
-            .for_each(|entity| {
-                entities_to_keep.insert(*entity);
-            });
-
-        let mut cb = CommandBuffer::new(&self.ecs);
-        for entity in Entity::query().iter(&self.ecs) {
-            if !entities_to_keep.contains(entity) {
-                cb.remove(*entity);
-            }
-        }
-        cb.flush(&mut self.ecs, &mut self.resources);
+        let mut level_ecs = std::mem::take(&mut self.ecs);
+        self.ecs.move_from(
+            &mut level_ecs,
+            &(component::<Player>() | component::<Carried>() | component::<Equipped>()),
+        );
 
-        <&mut FieldOfView>::query()
-            .iter_mut(&mut self.ecs)
-            .for_each(|fov| fov.is_dirty = true);
+        let mut dungeon = self.resources.remove::<Dungeon>().unwrap();
+        let old_level = <&Player>::query().iter(&self.ecs).next().unwrap().map_level;
+        let new_level = (old_level as i32 + delta) as u32;
+        dungeon.store(
+            old_level,
+            StoredLevel {
+                map: self.resources.remove::<Map>().unwrap(),
+                theme: self.resources.remove::<Box<dyn MapTheme>>().unwrap().kind(),
+                ecs: level_ecs,
+            },
+        );
 
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
+        let (map, theme, player_start) = if let Some(mut level) = dungeon.take(new_level) {
+            self.ecs.move_from(&mut level.ecs, &any());
+            let arrival = if delta > 0 {
+                TileType::UpStairs
+            } else {
+                TileType::Exit
+            };
+            let player_start = level.map.position_of(arrival).unwrap();
+            (level.map, level.theme.theme(), player_start)
+        } else {
+            self.generate_level(new_level)?
+        };
 
-        let mut map_level = 0;
         <(&mut Player, &mut Point)>::query()
             .iter_mut(&mut self.ecs)
             .for_each(|(player, pos)| {
-                player.map_level += 1;
-                map_level = player.map_level;
-                *pos = map_builder.player_start;
+                player.map_level = new_level;
+                *pos = player_start;
             });
-        if map_level == 2 {
+        <&mut FieldOfView>::query()
+            .iter_mut(&mut self.ecs)
+            .for_each(|fov| fov.is_dirty = true);
+
+        self.resources.insert(dungeon);
+        self.resources.insert(Camera::new(player_start, &map));
+        self.resources.insert(map);
+        self.resources
+            .insert(PathfindingCache::new(PATHFINDING_CACHE_SIZE));
+        self.resources.insert(TurnState::AwaitingInput);
+        self.resources.insert(theme);
+        Ok(())
+    }
+
+    fn generate_level(
+        &mut self,
+        map_level: u32,
+    ) -> Result<(Map, Box<dyn MapTheme>, Point), String> {
+        let mut rng = self.resources.remove::<RandomNumberGenerator>().unwrap();
+        let mut map_builder =
+            MapBuilder::new(&mut rng, &self.architects, &self.vaults, map_level as usize)?;
+        if map_level as usize == NUM_LEVELS - 1 {
             spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
         } else {
-            let exit_idx = Map::map_idx(map_builder.amulet_start);
+            let exit_idx = map_builder.map.map_idx(map_builder.amulet_start);
             map_builder.map.tiles[exit_idx] = TileType::Exit;
         }
+        map_builder.map.diagonal_movement = self.diagonal_movement;
+        if map_level > 0 {
+            let up_stairs_idx = map_builder.map.map_idx(map_builder.player_start);
+            map_builder.map.tiles[up_stairs_idx] = TileType::UpStairs;
+        }
         spawn_level(
             &mut self.ecs,
             &mut self.resources,
+            &self.templates,
             &mut rng,
             map_level as usize,
             &map_builder.entity_spawns,
+            &map_builder.vault_spawns,
         );
-        self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
-        self.resources.insert(TurnState::AwaitingInput);
-        self.resources.insert(map_builder.theme);
+        self.resources.insert(rng);
+        Ok((map_builder.map, map_builder.theme, map_builder.player_start))
     }
-}
 
-impl GameState for State {
-    fn tick(&mut self, ctx: &mut BTerm) {
-        ctx.set_active_console(0);
-        self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
-        ctx.cls();
-        ctx.set_active_console(1);
-        ctx.cls();
-        ctx.set_active_console(2);
-        ctx.cls();
-        self.resources.insert(ctx.key);
-        let current_state = *self.resources.get::<TurnState>().unwrap();
+    fn execute(&mut self, current_state: TurnState) -> Result<(), String> {
         match current_state {
             TurnState::AwaitingInput => self
                 .input_systems
@@ -233,10 +326,46 @@ impl GameState for State {
             TurnState::MonsterTurn => self
                 .monster_systems
                 .execute(&mut self.ecs, &mut self.resources),
+            TurnState::NextLevel => return self.change_level(1),
+            TurnState::PreviousLevel => return self.change_level(-1),
+            TurnState::GameOver | TurnState::Victory => {}
+        };
+        Ok(())
+    }
+}
+
+impl GameState for State {
+    fn tick(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(0);
+        let mouse_pos = Point::from_tuple(ctx.mouse_pos());
+        self.resources.insert(mouse_pos);
+        self.resources
+            .insert(MouseClick(ctx.left_click.then_some(mouse_pos)));
+        ctx.cls();
+        ctx.set_active_console(1);
+        ctx.cls();
+        ctx.set_active_console(2);
+        ctx.cls();
+        let action = ctx.key.and_then(|key| self.keymap.action(key));
+        self.resources.insert(action);
+        let current_state = *self.resources.get::<TurnState>().unwrap();
+        if current_state == TurnState::AwaitingInput && action == Some(Action::Save) {
+            match self.save() {
+                Ok(()) => ctx.quit(),
+                Err(err) => println!("Unable to save game: {}", err),
+            }
+            return;
+        }
+        let result = match current_state {
             TurnState::GameOver => self.game_over(ctx),
             TurnState::Victory => self.victory(ctx),
-            TurnState::NextLevel => self.advance_level(),
+            _ => self.execute(current_state),
         };
+        if let Err(err) = result {
+            println!("Unable to build a level: {}", err);
+            ctx.quit();
+            return;
+        }
         let mut draw_batch = DrawBatch::new();
         draw_batch.target(2);
         draw_batch.print_color_right(
@@ -250,6 +379,64 @@ impl GameState for State {
 }
 
 fn main() -> BError {
+    let options = Options::from_args()?;
+    if options.validate_templates {
+        let templates = Templates::load()?;
+        let mut problems = templates.validate();
+        match VaultLibrary::load() {
+            Ok(vaults) => problems.extend(
+                vaults
+                    .template_problems(&templates)
+                    .into_iter()
+                    .map(|problem| format!("{}: {}", VAULT_FILE, problem)),
+            ),
+            Err(err) => problems.push(err),
+        }
+        problems.iter().for_each(|problem| println!("{}", problem));
+        return if problems.is_empty() {
+            println!("Templates are valid.");
+            Ok(())
+        } else {
+            Err(format!("Found {} problems in the templates", problems.len()).into())
+        };
+    }
+    let mut architects = ArchitectRegistry::load()?;
+    if let Some(name) = &options.architect {
+        architects.force(name)?;
+    }
+    let vaults = VaultLibrary::load()?;
+    if let Some(levels) = options.benchmark_levels {
+        return Ok(benchmark::run_benchmark(
+            &architects,
+            &vaults,
+            options.seed,
+            levels,
+        )?);
+    }
+    if let Some(runs) = options.check_architects {
+        let problems = check_architects(&architects, &vaults, options.seed, runs);
+        problems.iter().for_each(|problem| println!("{}", problem));
+        return if problems.is_empty() {
+            println!("All architects passed {} runs.", runs);
+            Ok(())
+        } else {
+            Err(format!("Found {} problems in the architects", problems.len()).into())
+        };
+    }
+    let templates = Templates::load()?;
+    let keymap = Keymap::load()?;
+    vaults.check_templates(&templates)?;
+    if let Some(turns) = options.headless_turns {
+        return Ok(headless::run_headless(
+            templates,
+            architects,
+            vaults,
+            keymap,
+            options.seed,
+            turns,
+            options.diagonal_movement,
+        )?);
+    }
     let context = BTermBuilder::new()
         .with_title("Dungeon Crawler")
         .with_fps_cap(30.0)
@@ -263,5 +450,17 @@ fn main() -> BError {
         .with_simple_console_no_bg(SCREEN_WIDTH * 2, SCREEN_HEIGHT * 2, "terminal8x8.png")
         .with_fullscreen(true)
         .build()?;
-    main_loop(context, State::new())
+    let state = if options.load {
+        State::load(templates, architects, vaults, keymap, SaveGame::read()?)
+    } else {
+        State::new(
+            templates,
+            architects,
+            vaults,
+            keymap,
+            options.seed,
+            options.diagonal_movement,
+        )?
+    };
+    main_loop(context, state)
 }
@@ -1,15 +1,65 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
+const NEIGHBOR_OFFSETS: [Point; 8] = [
+    Point { x: -1, y: -1 },
+    Point { x: 0, y: -1 },
+    Point { x: 1, y: -1 },
+    Point { x: -1, y: 0 },
+    Point { x: 1, y: 0 },
+    Point { x: -1, y: 1 },
+    Point { x: 0, y: 1 },
+    Point { x: 1, y: 1 },
+];
 
-#[derive(Copy, Clone, PartialEq)]
+/// What a diagonal step costs a planned path, relative to an orthogonal one.
+const DIAGONAL_COST: f32 = 1.41;
+
+const ORTHOGONAL_OFFSETS: [Point; 4] = [
+    Point { x: -1, y: 0 },
+    Point { x: 1, y: 0 },
+    Point { x: 0, y: -1 },
+    Point { x: 0, y: 1 },
+];
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
     Exit,
+    UpStairs,
+    DoorClosed,
+    DoorOpen,
+    ShallowWater,
+    Rubble,
+    DeepWater,
+}
+
+impl TileType {
+    /// Whether anything can walk onto the tile. Deep water blocks movement but not sight.
+    pub fn is_passable(self) -> bool {
+        matches!(
+            self,
+            TileType::Floor
+                | TileType::Exit
+                | TileType::UpStairs
+                | TileType::DoorOpen
+                | TileType::ShallowWater
+                | TileType::Rubble
+        )
+    }
+
+    /// What stepping onto the tile costs a planned path, in steps over plain floor.
+    pub fn movement_cost(self) -> f32 {
+        match self {
+            TileType::ShallowWater => 2.0,
+            TileType::Rubble => 3.0,
+            _ => 1.0,
+        }
+    }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Revealed {
     Unrevealable,
     Unrevealed,
@@ -17,127 +67,155 @@ pub enum Revealed {
     FromMap,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Map {
+    pub width: i32,
+    pub height: i32,
     pub tiles: Vec<TileType>,
     pub revealed_tiles: Vec<Revealed>,
-    pub dijkstra_maps: Vec<Option<DijkstraMap>>,
+    /// Whether single steps may also be taken diagonally.
+    #[serde(default)]
+    pub diagonal_movement: bool,
 }
 
 impl Map {
-    pub fn new() -> Self {
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = (width * height) as usize;
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES],
-            revealed_tiles: vec![Revealed::Unrevealable; NUM_TILES],
-            dijkstra_maps: (0..NUM_TILES).map(|_| None).collect(),
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed_tiles: vec![Revealed::Unrevealable; num_tiles],
+            diagonal_movement: false,
         }
     }
 
     pub fn update_revealability(&mut self) {
-        let tiles = &self.tiles;
-        let revealed = &mut self.revealed_tiles;
-        tiles.iter().enumerate().for_each(|(idx, tile)| {
+        let mut revealed = std::mem::take(&mut self.revealed_tiles);
+        self.tiles.iter().enumerate().for_each(|(idx, tile)| {
             if !Self::is_opaque(*tile) {
-                let point_for_idx = Self::map_point(idx);
-                let offsets = [
-                    Point::new(-1, -1),
-                    Point::new(0, -1),
-                    Point::new(1, -1),
-                    Point::new(-1, 0),
-                    Point::new(0, 0),
-                    Point::new(1, 0),
-                    Point::new(-1, 1),
-                    Point::new(0, 1),
-                    Point::new(1, 1),
-                ];
-                for offset in offsets {
-                    let point = point_for_idx + offset;
-                    let idx_for_point = Self::map_idx(point);
-                    revealed[idx_for_point] = Revealed::Unrevealed;
-                }
+                revealed[idx] = Revealed::Unrevealed;
+                self.neighbors(self.map_point(idx))
+                    .for_each(|neighbor| revealed[self.map_idx(neighbor)] = Revealed::Unrevealed);
             }
         });
+        self.revealed_tiles = revealed;
+    }
+
+    pub fn in_bounds(&self, point: Point) -> bool {
+        point.x >= 0 && point.x < self.width && point.y >= 0 && point.y < self.height
+    }
+
+    /// The up to eight tiles surrounding `point` that lie on the map, so callers never index
+    /// past an edge or wrap around onto the next row.
+    pub fn neighbors(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        NEIGHBOR_OFFSETS
+            .iter()
+            .map(move |offset| point + *offset)
+            .filter(move |neighbor| self.in_bounds(*neighbor))
+    }
+
+    /// The up to four tiles orthogonally adjacent to `point` that lie on the map.
+    pub fn orthogonal_neighbors(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        ORTHOGONAL_OFFSETS
+            .iter()
+            .map(move |offset| point + *offset)
+            .filter(move |neighbor| self.in_bounds(*neighbor))
+    }
+
+    /// The offsets of a single step: all eight directions with diagonal movement, otherwise the
+    /// four orthogonal ones.
+    pub fn step_offsets(&self) -> &'static [Point] {
+        if self.diagonal_movement {
+            &NEIGHBOR_OFFSETS
+        } else {
+            &ORTHOGONAL_OFFSETS
+        }
     }
 
-    pub fn in_bounds(point: Point) -> bool {
-        point.x >= 0 && point.x < SCREEN_WIDTH && point.y >= 0 && point.y < SCREEN_HEIGHT
+    /// The tiles a single step from `point` can reach that lie on the map.
+    pub fn steps(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        self.step_offsets()
+            .iter()
+            .map(move |offset| point + *offset)
+            .filter(move |neighbor| self.in_bounds(*neighbor))
+    }
+
+    /// Whether a single step leads from `from` to `to`, such as for a melee attack.
+    pub fn is_adjacent(&self, from: Point, to: Point) -> bool {
+        self.step_offsets().contains(&(to - from))
     }
 
     pub fn can_enter_tile(&self, point: Point) -> bool {
-        let tile_type = self.tiles[Self::map_idx(point)];
-        Self::in_bounds(point) && [TileType::Floor, TileType::Exit].contains(&tile_type)
+        self.in_bounds(point) && self.tiles[self.map_idx(point)].is_passable()
+    }
+
+    /// A view of the map for pathfinding on behalf of something that can open doors, in which
+    /// closed doors lead on like open ones.
+    pub fn for_door_openers(&self) -> DoorOpenerMap<'_> {
+        DoorOpenerMap { map: self }
+    }
+
+    fn exits(&self, idx: usize, through_doors: bool) -> SmallVec<[(usize, f32); 10]> {
+        let point = self.map_point(idx);
+        self.steps(point)
+            .filter(|destination| {
+                self.can_enter_tile(*destination)
+                    || (through_doors
+                        && self.tiles[self.map_idx(*destination)] == TileType::DoorClosed)
+            })
+            .map(|destination| {
+                let idx = self.map_idx(destination);
+                let cost = self.tiles[idx].movement_cost();
+                if destination.x != point.x && destination.y != point.y {
+                    (idx, cost * DIAGONAL_COST)
+                } else {
+                    (idx, cost)
+                }
+            })
+            .collect()
     }
 
-    pub fn map_idx(point: Point) -> usize {
-        ((point.y * SCREEN_WIDTH) + point.x) as usize
+    pub fn map_idx(&self, point: Point) -> usize {
+        ((point.y * self.width) + point.x) as usize
     }
 
-    pub fn map_point(idx: usize) -> Point {
-        Point::new(idx % SCREEN_WIDTH as usize, idx / SCREEN_WIDTH as usize)
+    pub fn map_point(&self, idx: usize) -> Point {
+        Point::new(idx % self.width as usize, idx / self.width as usize)
     }
 
-    pub fn try_idx(point: Point) -> Option<usize> {
-        if !Self::in_bounds(point) {
+    pub fn try_idx(&self, point: Point) -> Option<usize> {
+        if !self.in_bounds(point) {
             None
         } else {
-            Some(Self::map_idx(point))
+            Some(self.map_idx(point))
         }
     }
 
-    fn valid_exit(&self, loc: Point, delta: Point) -> Option<usize> {
-        let destination = loc + delta;
-        if Self::in_bounds(destination) {
-            if self.can_enter_tile(destination) {
-                let idx = self.point2d_to_index(destination);
-                Some(idx)
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+    pub fn position_of(&self, tile_type: TileType) -> Option<Point> {
+        self.tiles
+            .iter()
+            .position(|tile| *tile == tile_type)
+            .map(|idx| self.map_point(idx))
+    }
+
+    pub fn line_of_sight(&self, from: Point, to: Point) -> bool {
+        line2d_bresenham(from, to)
+            .iter()
+            .filter(|point| **point != from && **point != to)
+            .all(|point| {
+                self.in_bounds(*point) && !Self::is_opaque(self.tiles[self.map_idx(*point)])
+            })
     }
 
     fn is_opaque(tile_type: TileType) -> bool {
-        tile_type == TileType::Wall
-    }
-
-    pub fn update_dijkstra_maps(&mut self) {
-        let tiles = &self.tiles;
-        let mut dijkstra_maps: Vec<Option<DijkstraMap>> = (0..NUM_TILES).map(|_| None).collect();
-        tiles.iter().enumerate().for_each(|(idx, _)| {
-            if self.can_enter_tile(Self::map_point(idx)) {
-                let target = vec![idx];
-                dijkstra_maps[idx] = Some(DijkstraMap::new(
-                    SCREEN_WIDTH,
-                    SCREEN_HEIGHT,
-                    &target,
-                    self,
-                    1024.0,
-                ));
-            };
-        });
-        self.dijkstra_maps = dijkstra_maps;
+        matches!(tile_type, TileType::Wall | TileType::DoorClosed)
     }
 }
 
 impl BaseMap for Map {
     fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
-        let mut exits = SmallVec::new();
-        let location = self.index_to_point2d(idx);
-
-        if let Some(target_idx) = self.valid_exit(location, Point::new(-1, 0)) {
-            exits.push((target_idx, 1.0));
-        }
-        if let Some(target_idx) = self.valid_exit(location, Point::new(1, 0)) {
-            exits.push((target_idx, 1.0));
-        }
-        if let Some(target_idx) = self.valid_exit(location, Point::new(0, -1)) {
-            exits.push((target_idx, 1.0));
-        }
-        if let Some(target_idx) = self.valid_exit(location, Point::new(0, 1)) {
-            exits.push((target_idx, 1.0));
-        }
-        exits
+        self.exits(idx, false)
     }
 
     fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
@@ -151,10 +229,114 @@ impl BaseMap for Map {
 
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
-        Point::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+        Point::new(self.width, self.height)
     }
 
     fn in_bounds(&self, pos: Point) -> bool {
-        Self::in_bounds(pos)
+        Map::in_bounds(self, pos)
+    }
+}
+
+pub struct DoorOpenerMap<'a> {
+    map: &'a Map,
+}
+
+impl BaseMap for DoorOpenerMap<'_> {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        self.map.exits(idx, true)
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn is_opaque(&self, idx: usize) -> bool {
+        BaseMap::is_opaque(self.map, idx)
+    }
+}
+
+impl Algorithm2D for DoorOpenerMap<'_> {
+    fn dimensions(&self) -> Point {
+        self.map.dimensions()
+    }
+
+    fn in_bounds(&self, pos: Point) -> bool {
+        self.map.in_bounds(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: i32 = 7;
+    const HEIGHT: i32 = 5;
+
+    fn points() -> impl Iterator<Item = Point> {
+        (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| Point::new(x, y)))
+    }
+
+    /// Whether `b` is one of the up to eight tiles touching `a`, without wrapping.
+    fn touching(a: Point, b: Point) -> bool {
+        a != b && (a.x - b.x).abs() <= 1 && (a.y - b.y).abs() <= 1
+    }
+
+    #[test]
+    fn neighbors_stay_on_the_map_without_wrapping() {
+        let map = Map::new(WIDTH, HEIGHT);
+        for point in points() {
+            let neighbors: Vec<Point> = map.neighbors(point).collect();
+            let expected: Vec<Point> = points().filter(|p| touching(point, *p)).collect();
+            assert_eq!(neighbors.len(), expected.len(), "{:?}", point);
+            assert!(
+                neighbors.iter().all(|n| expected.contains(n)),
+                "{:?}",
+                point
+            );
+        }
+    }
+
+    #[test]
+    fn exits_stay_on_the_map_without_wrapping() {
+        for diagonal_movement in [false, true] {
+            let mut map = Map::new(WIDTH, HEIGHT);
+            map.diagonal_movement = diagonal_movement;
+            for point in points() {
+                let exits = map.get_available_exits(map.map_idx(point));
+                let expected = points()
+                    .filter(|p| touching(point, *p))
+                    .filter(|p| diagonal_movement || p.x == point.x || p.y == point.y)
+                    .count();
+                assert_eq!(exits.len(), expected, "{:?}", point);
+                assert!(exits
+                    .iter()
+                    .all(|(idx, _)| map.is_adjacent(point, map.map_point(*idx))));
+            }
+        }
+    }
+
+    #[test]
+    fn revealability_does_not_wrap() {
+        for floor in points() {
+            let mut map = Map::new(WIDTH, HEIGHT);
+            map.tiles.fill(TileType::Wall);
+            let idx = map.map_idx(floor);
+            map.tiles[idx] = TileType::Floor;
+            map.update_revealability();
+            for point in points() {
+                let expected = if point == floor || touching(floor, point) {
+                    Revealed::Unrevealed
+                } else {
+                    Revealed::Unrevealable
+                };
+                assert_eq!(
+                    map.revealed_tiles[map.map_idx(point)],
+                    expected,
+                    "{:?} from floor at {:?}",
+                    point,
+                    floor
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,201 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+const MIN_LEAF_SIZE: i32 = 8;
+const MIN_ROOM_SIZE: i32 = 3;
+
+pub struct BspArchitect {}
+
+impl MapArchitect for BspArchitect {
+    fn new(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> Option<MapBuilder> {
+        let mut mb = MapBuilder {
+            map: Map::new(width, height),
+            rooms: Vec::new(),
+            entity_spawns: Vec::new(),
+            vault_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: random_theme(rng),
+        };
+        mb.fill(TileType::Wall);
+        let mut doorways = Vec::new();
+        Self::partition(
+            Rect::with_exact(1, 1, width - 1, height - 1),
+            rng,
+            &mut mb,
+            &mut doorways,
+        );
+        for point in doorways {
+            if mb.is_doorway(point) {
+                let idx = mb.map.map_idx(point);
+                mb.map.tiles[idx] = TileType::DoorClosed;
+            }
+        }
+        mb.player_start = mb.rooms[0].center();
+        Some(mb)
+    }
+}
+
+impl BspArchitect {
+    /// Splits `area` in two until the pieces are too small to split again, places a room in
+    /// each leaf, and joins the two halves of every split with a corridor between their closest
+    /// rooms, noting where each corridor leaves and enters a room in `doorways`. Returns the
+    /// indices into `mb.rooms` of the rooms inside `area`.
+    fn partition(
+        area: Rect,
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+        doorways: &mut Vec<Point>,
+    ) -> Vec<usize> {
+        let can_split_x = area.width() >= MIN_LEAF_SIZE * 2;
+        let can_split_y = area.height() >= MIN_LEAF_SIZE * 2;
+        let split_x = match (can_split_x, can_split_y) {
+            (false, false) => return vec![Self::place_room(area, rng, mb)],
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => {
+                if area.width() * 4 > area.height() * 5 {
+                    true
+                } else if area.height() * 4 > area.width() * 5 {
+                    false
+                } else {
+                    rng.range(0, 2) == 0
+                }
+            }
+        };
+
+        let (first, second) = if split_x {
+            let x = rng.range(area.x1 + MIN_LEAF_SIZE, area.x2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, x, area.y2),
+                Rect::with_exact(x, area.y1, area.x2, area.y2),
+            )
+        } else {
+            let y = rng.range(area.y1 + MIN_LEAF_SIZE, area.y2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, area.x2, y),
+                Rect::with_exact(area.x1, y, area.x2, area.y2),
+            )
+        };
+
+        let mut rooms = Self::partition(first, rng, mb, doorways);
+        let second_rooms = Self::partition(second, rng, mb, doorways);
+        doorways.extend(Self::connect_closest(&rooms, &second_rooms, rng, mb));
+        rooms.extend(second_rooms);
+        rooms
+    }
+
+    /// Places a room inside the leaf, one tile in from each side so rooms in neighbouring
+    /// leaves never touch.
+    fn place_room(leaf: Rect, rng: &mut RandomNumberGenerator, mb: &mut MapBuilder) -> usize {
+        let width = rng.range(MIN_ROOM_SIZE, leaf.width() - 1);
+        let height = rng.range(MIN_ROOM_SIZE, leaf.height() - 1);
+        let room = Rect::with_size(
+            rng.range(leaf.x1 + 1, leaf.x2 - width),
+            rng.range(leaf.y1 + 1, leaf.y2 - height),
+            width,
+            height,
+        );
+        room.for_each(|p| {
+            let idx = mb.map.map_idx(p);
+            mb.map.tiles[idx] = TileType::Floor;
+        });
+        mb.rooms.push(room);
+        mb.rooms.len() - 1
+    }
+
+    /// Joins two sibling partitions with an L-shaped corridor between their closest pair of
+    /// rooms. Returns the corridor tiles just outside each of the two rooms, where the doors go
+    /// once every corridor has been dug.
+    fn connect_closest(
+        first: &[usize],
+        second: &[usize],
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+    ) -> [Point; 2] {
+        let rooms = &mb.rooms;
+        let (from_room, to_room) = first
+            .iter()
+            .flat_map(|a| second.iter().map(move |b| (rooms[*a], rooms[*b])))
+            .min_by(|(a1, b1), (a2, b2)| {
+                let d1 = DistanceAlg::Pythagoras.distance2d(a1.center(), b1.center());
+                let d2 = DistanceAlg::Pythagoras.distance2d(a2.center(), b2.center());
+                d1.partial_cmp(&d2).unwrap()
+            })
+            .unwrap();
+        let (from, to) = (from_room.center(), to_room.center());
+
+        let corner = if rng.range(0, 2) == 1 {
+            mb.apply_horizontal_tunnel(from.x, to.x, from.y);
+            mb.apply_vertical_tunnel(from.y, to.y, to.x);
+            Point::new(to.x, from.y)
+        } else {
+            mb.apply_vertical_tunnel(from.y, to.y, from.x);
+            mb.apply_horizontal_tunnel(from.x, to.x, to.y);
+            Point::new(from.x, to.y)
+        };
+
+        let corridor: Vec<Point> = Self::straight(from, corner)
+            .chain(Self::straight(corner, to))
+            .collect();
+        let leaves = *corridor
+            .iter()
+            .find(|p| !from_room.point_in_rect(**p))
+            .unwrap();
+        let enters = *corridor
+            .iter()
+            .rev()
+            .find(|p| !to_room.point_in_rect(**p))
+            .unwrap();
+        [leaves, enters]
+    }
+
+    /// The tiles from `start` up to but not including `end`, which share a row or a column.
+    fn straight(start: Point, end: Point) -> impl Iterator<Item = Point> {
+        let step = Point::new((end.x - start.x).signum(), (end.y - start.y).signum());
+        let length = i32::max((end.x - start.x).abs(), (end.y - start.y).abs());
+        (0..length).map(move |i| start + step * i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_are_joined_through_doors() {
+        for seed in 0..20 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mb = BspArchitect {}
+                .new(&mut rng, SCREEN_WIDTH, SCREEN_HEIGHT)
+                .unwrap();
+            let start = mb.map.map_idx(mb.player_start);
+            let through_doors = DijkstraMap::new(
+                mb.map.width,
+                mb.map.height,
+                &[start],
+                &mb.map.for_door_openers(),
+                PATHFINDING_MAX_DEPTH,
+            );
+            let doors_closed = DijkstraMap::new(
+                mb.map.width,
+                mb.map.height,
+                &[start],
+                &mb.map,
+                PATHFINDING_MAX_DEPTH,
+            );
+            let centers: Vec<usize> = mb
+                .rooms
+                .iter()
+                .map(|room| mb.map.map_idx(room.center()))
+                .collect();
+            assert!(centers.iter().all(|idx| through_doors.map[*idx] < f32::MAX));
+            assert!(centers.iter().any(|idx| doors_closed.map[*idx] == f32::MAX));
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use super::{map_dimensions, ArchitectRegistry, MapBuilder, VaultLibrary};
+use crate::prelude::*;
+
+fn check_point(map: &Map, point: Point, what: &str, problems: &mut Vec<String>) {
+    if !map.in_bounds(point) {
+        problems.push(format!("{} {:?} is off the map", what, point));
+    } else if !map.can_enter_tile(point) {
+        problems.push(format!("{} {:?} is not on an enterable tile", what, point));
+    }
+}
+
+fn check_map_builder(mb: &MapBuilder, dimensions: Point) -> Vec<String> {
+    let mut problems = Vec::new();
+    let num_tiles = (dimensions.x * dimensions.y) as usize;
+    if mb.map.width != dimensions.x || mb.map.height != dimensions.y {
+        problems.push(format!(
+            "Map is {}x{}, expected {}x{}",
+            mb.map.width, mb.map.height, dimensions.x, dimensions.y
+        ));
+    }
+    if mb.map.tiles.len() != num_tiles || mb.map.revealed_tiles.len() != num_tiles {
+        problems.push(format!(
+            "Map has {} tiles and {} revealed tiles, expected {}",
+            mb.map.tiles.len(),
+            mb.map.revealed_tiles.len(),
+            num_tiles
+        ));
+        return problems;
+    }
+
+    check_point(&mb.map, mb.player_start, "Player start", &mut problems);
+    check_point(&mb.map, mb.amulet_start, "Amulet start", &mut problems);
+    mb.entity_spawns
+        .iter()
+        .for_each(|spawn| check_point(&mb.map, *spawn, "Entity spawn", &mut problems));
+    mb.vault_spawns.iter().for_each(|(spawn, name)| {
+        check_point(&mb.map, *spawn, &format!("{} spawn", name), &mut problems)
+    });
+    if !problems.is_empty() {
+        return problems;
+    }
+
+    let dijkstra_map = DijkstraMap::new(
+        mb.map.width,
+        mb.map.height,
+        &[mb.map.map_idx(mb.player_start)],
+        &mb.map.for_door_openers(),
+        PATHFINDING_MAX_DEPTH,
+    );
+    if dijkstra_map.map[mb.map.map_idx(mb.amulet_start)] == f32::MAX {
+        problems.push("Amulet start cannot be reached from the player start".to_string());
+    }
+    problems
+}
+
+/// Runs every pipeline in the registry on each level for the given number of seeds, including
+/// those that never build a level, and checks the maps they build. Returns one line per problem.
+pub fn check_architects(
+    architects: &ArchitectRegistry,
+    vaults: &VaultLibrary,
+    seed: u64,
+    runs: usize,
+) -> Vec<String> {
+    let mut rng = RandomNumberGenerator::seeded(seed);
+    let mut problems = Vec::new();
+    for _ in 0..runs {
+        let run_seed = rng.next_u64();
+        for level in 0..NUM_LEVELS {
+            let dimensions = map_dimensions(level);
+            for pipeline in architects.pipelines() {
+                let mut pipeline_rng = RandomNumberGenerator::seeded(run_seed);
+                let level_problems = match pipeline.build(&mut pipeline_rng, vaults, level) {
+                    Some(mut mb) => {
+                        mb.map.update_revealability();
+                        check_map_builder(&mb, dimensions)
+                    }
+                    None => vec!["Architect could not build a map".to_string()],
+                };
+                level_problems.into_iter().for_each(|problem| {
+                    problems.push(format!(
+                        "[{} level {} seed {}] {}",
+                        pipeline.name, level, run_seed, problem
+                    ))
+                });
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::automata::CellularAutomataArchitect;
+    use super::*;
+
+    const KINDS: [ArchitectKind; 6] = [
+        ArchitectKind::Empty,
+        ArchitectKind::Rooms,
+        ArchitectKind::CellularAutomata,
+        ArchitectKind::DrunkardsWalk,
+        ArchitectKind::Bsp,
+        ArchitectKind::WaveFunctionCollapse,
+    ];
+    const SEEDS: u64 = 10;
+
+    /// The architect's map, if it built one, with every edge tile opened up, so floor runs from
+    /// edge to edge.
+    fn edge_to_edge(kind: ArchitectKind, seed: u64, level: usize) -> Option<Map> {
+        let dimensions = map_dimensions(level);
+        let mut rng = RandomNumberGenerator::seeded(seed);
+        let mut map = kind
+            .architect()
+            .new(&mut rng, dimensions.x, dimensions.y)?
+            .map;
+        for idx in 0..map.tiles.len() {
+            let point = map.map_point(idx);
+            if point.x == 0 || point.y == 0 || point.x == map.width - 1 || point.y == map.height - 1
+            {
+                map.tiles[idx] = TileType::Floor;
+            }
+        }
+        Some(map)
+    }
+
+    /// The tiles touching `point` that lie on the map, found without the neighbour API.
+    fn touching(map: &Map, point: Point) -> Vec<Point> {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| Point::new(dx, dy)))
+            .filter(|offset| *offset != Point::zero())
+            .map(|offset| point + offset)
+            .filter(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height)
+            .collect()
+    }
+
+    #[test]
+    fn architect_maps_stay_in_bounds_from_edge_to_edge() {
+        for kind in KINDS {
+            for seed in 0..SEEDS {
+                for level in 0..NUM_LEVELS {
+                    let Some(mut map) = edge_to_edge(kind, seed, level) else {
+                        continue;
+                    };
+                    map.update_revealability();
+                    for idx in 0..map.tiles.len() {
+                        let point = map.map_point(idx);
+                        let touching = touching(&map, point);
+
+                        let walls = touching
+                            .iter()
+                            .filter(|p| map.tiles[map.map_idx(**p)] == TileType::Wall)
+                            .count();
+                        assert_eq!(
+                            CellularAutomataArchitect::count_neighbors(point, &map),
+                            walls,
+                            "{:?} seed {} level {} at {:?}",
+                            kind,
+                            seed,
+                            level,
+                            point
+                        );
+
+                        let seen_from = std::iter::once(point)
+                            .chain(touching.iter().copied())
+                            .any(|p| !BaseMap::is_opaque(&map, map.map_idx(p)));
+                        let revealable = map.revealed_tiles[idx] == Revealed::Unrevealed;
+                        assert_eq!(
+                            revealable, seen_from,
+                            "{:?} seed {} level {} at {:?}",
+                            kind, seed, level, point
+                        );
+
+                        for diagonal_movement in [false, true] {
+                            map.diagonal_movement = diagonal_movement;
+                            for (exit, _) in map.get_available_exits(idx) {
+                                let exit = map.map_point(exit);
+                                assert!(
+                                    touching.contains(&exit)
+                                        && map.is_adjacent(point, exit)
+                                        && map.can_enter_tile(exit),
+                                    "{:?} seed {} level {}: {:?} has exit {:?}",
+                                    kind,
+                                    seed,
+                                    level,
+                                    point,
+                                    exit
+                                );
+                            }
+                        }
+                        map.diagonal_movement = false;
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn registered_pipelines_pass_the_checks() {
+        let architects = ArchitectRegistry::load().unwrap();
+        let vaults = VaultLibrary::load().unwrap();
+        assert_eq!(
+            check_architects(&architects, &vaults, 1, 2),
+            Vec::<String>::new()
+        );
+    }
+}
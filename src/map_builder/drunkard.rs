@@ -4,25 +4,28 @@ use crate::prelude::*;
 pub struct DrunkardsWalkArchitect {}
 
 impl MapArchitect for DrunkardsWalkArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> Option<MapBuilder> {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(width, height),
             rooms: Vec::new(),
             entity_spawns: Vec::new(),
+            vault_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
             theme: random_theme(rng),
         };
         mb.fill(TileType::Wall);
-        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        let center = Point::new(width / 2, height / 2);
         self.drunkard(center, rng, &mut mb.map);
 
         loop {
             self.drunkard(
-                Point::new(
-                    rng.range(1, SCREEN_WIDTH - 1),
-                    rng.range(1, SCREEN_HEIGHT - 1),
-                ),
+                Point::new(rng.range(1, width - 1), rng.range(1, height - 1)),
                 rng,
                 &mut mb.map,
             );
@@ -33,28 +36,25 @@ impl MapArchitect for DrunkardsWalkArchitect {
                 }
             }
         }
-        mb.entity_spawns = mb.spawn_monsters(center, rng);
-        prefab::apply_prefab(&mut mb, rng);
         mb.player_start = center;
-        mb.amulet_start = mb.find_most_distant();
-        mb
+        Some(mb)
     }
 }
 
 impl DrunkardsWalkArchitect {
     const STAGGER_DISTANCE: usize = 400;
-    const NUM_TILES: usize = (SCREEN_HEIGHT * SCREEN_HEIGHT) as usize;
-    const DESIRED_FLOOR: usize = Self::NUM_TILES / 2;
+    const DESIRED_FLOOR_FRACTION: usize = 3;
 
     fn map_completed(map: &Map) -> bool {
-        map.tiles.iter().filter(|t| **t == TileType::Floor).count() >= Self::DESIRED_FLOOR
+        map.tiles.iter().filter(|t| **t == TileType::Floor).count()
+            >= map.tiles.len() / Self::DESIRED_FLOOR_FRACTION
     }
 
-    fn in_inner_bounds(point: Point) -> bool {
-        point.x >= 1 && point.x <= SCREEN_WIDTH - 2 && point.y >= 1 && point.y <= SCREEN_HEIGHT - 2
+    fn in_inner_bounds(point: Point, map: &Map) -> bool {
+        point.x >= 1 && point.x <= map.width - 2 && point.y >= 1 && point.y <= map.height - 2
     }
     fn drunkard(&mut self, start: Point, rng: &mut RandomNumberGenerator, map: &mut Map) {
-        let mut drunkard_pos = start.clone();
+        let mut drunkard_pos = start;
         let mut distance_staggered = 0;
 
         loop {
@@ -68,7 +68,7 @@ impl DrunkardsWalkArchitect {
                 _ => Point::new(0, -1),
             };
 
-            if !Self::in_inner_bounds(drunkard_pos) {
+            if !Self::in_inner_bounds(drunkard_pos, map) {
                 break;
             }
 
@@ -1,27 +1,59 @@
 mod automata;
+mod bsp;
+mod check;
 mod drunkard;
 mod empty;
+mod pipeline;
 pub mod prefab;
+mod registry;
 mod rooms;
 mod themes;
+mod wfc;
 
 use crate::prelude::*;
-use automata::CellularAutomataArchitect;
-use drunkard::DrunkardsWalkArchitect;
-use rooms::RoomsArchitect;
+pub use check::check_architects;
+pub use prefab::{VaultLibrary, VAULT_FILE};
+pub use registry::{ArchitectKind, ArchitectRegistry};
 use themes::DungeonTheme;
 use themes::ForestTheme;
+pub use themes::ThemeKind;
 
 const NUM_ROOMS: usize = 20;
+/// The most spawn points a level hands out for templates.
+pub const NUM_MONSTERS: usize = 50;
+const MAP_WIDTH: i32 = 80;
+const MAP_HEIGHT: i32 = 50;
+const MAP_GROWTH_PER_LEVEL: Point = Point { x: 16, y: 10 };
+const NUM_POOLS: usize = 4;
+const POOL_STEPS: usize = 60;
+/// Chance in one hundred that a floor tile hemmed in by walls turns to rubble.
+const RUBBLE_CHANCE: i32 = 25;
+/// How many times a level is rerolled before giving up on the architects.
+const MAX_BUILD_ATTEMPTS: usize = 100;
 
+/// Lays out a map's tiles and picks where the player starts. The rest of the map, such as
+/// spawns, the exit and vaults, is left to the steps of the pipeline the architect is part of.
+/// Returns `None` if the architect could not lay out a map worth playing.
 trait MapArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
+    #[allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
+    fn new(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> Option<MapBuilder>;
+}
+
+/// The size of the map on the given level; each level down is larger than the one above it.
+pub fn map_dimensions(level: usize) -> Point {
+    Point::new(MAP_WIDTH, MAP_HEIGHT) + MAP_GROWTH_PER_LEVEL * level as i32
 }
 
 pub struct MapBuilder {
     pub map: Map,
     pub rooms: Vec<Rect>,
     pub entity_spawns: Vec<Point>,
+    pub vault_spawns: Vec<(Point, String)>,
     pub player_start: Point,
     pub amulet_start: Point,
     pub theme: Box<dyn MapTheme>,
@@ -34,10 +66,10 @@ impl MapBuilder {
 
     fn find_most_distant(&self) -> Point {
         let dijkstra_map = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            self.map.width,
+            self.map.height,
             &[self.map.point2d_to_index(self.player_start)],
-            &self.map,
+            &self.map.for_door_openers(),
             1024.0,
         );
         const UNREACHABLE: &f32 = &f32::MAX;
@@ -46,18 +78,37 @@ impl MapBuilder {
                 .map
                 .iter()
                 .enumerate()
-                .filter(|(_, dist)| *dist < UNREACHABLE)
+                .filter(|(idx, dist)| *dist < UNREACHABLE && self.map.tiles[*idx].is_passable())
                 .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
                 .unwrap()
                 .0,
         )
     }
 
+    /// The floor tile closest to the middle of the map that has somewhere to go, if there is one.
+    fn find_central_start(&self) -> Option<Point> {
+        let map = &self.map;
+        let center = Point::new(map.width / 2, map.height / 2);
+        map.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| **t == TileType::Floor)
+            .filter(|(idx, _)| !map.get_available_exits(*idx).is_empty())
+            .map(|(idx, _)| {
+                (
+                    idx,
+                    DistanceAlg::Pythagoras.distance2d(center, map.index_to_point2d(idx)),
+                )
+            })
+            .min_by(|(_, distance), (_, distance2)| distance.partial_cmp(distance2).unwrap())
+            .map(|(idx, _)| map.index_to_point2d(idx))
+    }
+
     fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator) {
         while self.rooms.len() < NUM_ROOMS {
             let room = Rect::with_size(
-                rng.range(1, SCREEN_WIDTH - 10),
-                rng.range(1, SCREEN_HEIGHT - 10),
+                rng.range(1, self.map.width - 10),
+                rng.range(1, self.map.height - 10),
                 rng.range(2, 10),
                 rng.range(2, 10),
             );
@@ -69,8 +120,8 @@ impl MapBuilder {
             }
             if !overlap {
                 room.for_each(|p| {
-                    if p.x > 0 && p.x < SCREEN_WIDTH && p.y > 0 && p.y < SCREEN_HEIGHT {
-                        let idx = Map::map_idx(p);
+                    if p.x > 0 && p.x < self.map.width && p.y > 0 && p.y < self.map.height {
+                        let idx = self.map.map_idx(p);
                         self.map.tiles[idx] = TileType::Floor;
                     }
                 });
@@ -82,7 +133,7 @@ impl MapBuilder {
     fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
         use std::cmp::{max, min};
         for y in min(y1, y2)..=max(y1, y2) {
-            if let Some(idx) = Map::try_idx(Point::new(x, y)) {
+            if let Some(idx) = self.map.try_idx(Point::new(x, y)) {
                 self.map.tiles[idx] = TileType::Floor;
             }
         }
@@ -91,7 +142,7 @@ impl MapBuilder {
     fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
         use std::cmp::{max, min};
         for x in min(x1, x2)..=max(x1, x2) {
-            if let Some(idx) = Map::try_idx(Point::new(x, y)) {
+            if let Some(idx) = self.map.try_idx(Point::new(x, y)) {
                 self.map.tiles[idx] = TileType::Floor;
             }
         }
@@ -99,7 +150,7 @@ impl MapBuilder {
 
     fn build_corridors(&mut self, rng: &mut RandomNumberGenerator) {
         let mut rooms = self.rooms.clone();
-        rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|room| room.center().x);
         for (i, room) in rooms.iter().enumerate().skip(1) {
             let prev = rooms[i - 1].center();
             let new = room.center();
@@ -115,7 +166,6 @@ impl MapBuilder {
     }
 
     fn spawn_monsters(&self, start: Point, rng: &mut RandomNumberGenerator) -> Vec<Point> {
-        const NUM_MONSTERS: usize = 50;
         let mut spawnable_tiles: Vec<Point> = self
             .map
             .tiles
@@ -129,7 +179,7 @@ impl MapBuilder {
             .map(|(idx, _)| self.map.index_to_point2d(idx))
             .collect();
         let mut spawns = Vec::new();
-        for _ in 0..NUM_MONSTERS {
+        for _ in 0..NUM_MONSTERS.min(spawnable_tiles.len()) {
             let target_index = rng.random_slice_index(&spawnable_tiles).unwrap();
             spawns.push(spawnable_tiles[target_index]);
             spawnable_tiles.remove(target_index);
@@ -137,12 +187,205 @@ impl MapBuilder {
         spawns
     }
 
+    /// Spreads the monster spawns over every room but the first one, where the player starts,
+    /// handing them out one room at a time.
+    fn spawn_in_rooms(&self, rng: &mut RandomNumberGenerator) -> Vec<Point> {
+        let mut free_tiles: Vec<Vec<Point>> = self
+            .rooms
+            .iter()
+            .skip(1)
+            .map(|room| {
+                let mut tiles = Vec::new();
+                room.for_each(|p| {
+                    if self.map.in_bounds(p)
+                        && self.map.tiles[self.map.map_idx(p)] == TileType::Floor
+                    {
+                        tiles.push(p);
+                    }
+                });
+                tiles
+            })
+            .collect();
+
+        let mut spawns = Vec::new();
+        while spawns.len() < NUM_MONSTERS && free_tiles.iter().any(|tiles| !tiles.is_empty()) {
+            for tiles in free_tiles.iter_mut().filter(|tiles| !tiles.is_empty()) {
+                if spawns.len() == NUM_MONSTERS {
+                    break;
+                }
+                let idx = rng.random_slice_index(tiles).unwrap();
+                spawns.push(tiles.remove(idx));
+            }
+        }
+        spawns
+    }
+
+    /// One erosion pass: inner walls with fewer than five wall neighbours wear away into floor,
+    /// rounding off corners and thin walls without ever closing a passage.
+    fn erode(&mut self) {
+        let map = &self.map;
+        let mut new_tiles = map.tiles.clone();
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
+                let point = Point::new(x, y);
+                let walls = map
+                    .neighbors(point)
+                    .filter(|neighbor| map.tiles[map.map_idx(*neighbor)] == TileType::Wall)
+                    .count();
+                if walls < 5 {
+                    new_tiles[map.map_idx(point)] = TileType::Floor;
+                }
+            }
+        }
+        self.map.tiles = new_tiles;
+    }
+
+    /// A floor tile with walls on two opposite sides and floor on the other two.
+    fn is_doorway(&self, point: Point) -> bool {
+        let tile = |offset: Point| {
+            self.map
+                .try_idx(point + offset)
+                .map(|idx| self.map.tiles[idx])
+        };
+        let (wall, floor) = (Some(TileType::Wall), Some(TileType::Floor));
+        let (north, south) = (tile(Point::new(0, -1)), tile(Point::new(0, 1)));
+        let (west, east) = (tile(Point::new(-1, 0)), tile(Point::new(1, 0)));
+        tile(Point::zero()) == floor
+            && ((north == wall && south == wall && west == floor && east == floor)
+                || (west == wall && east == wall && north == floor && south == floor))
+    }
+
+    /// Closes every doorway just outside a room with a door, leaving the player start, the
+    /// exit and spawn points open and never putting two doors side by side.
+    fn place_doors(&mut self) {
+        let mut doors: Vec<Point> = Vec::new();
+        for room in self.rooms.iter() {
+            let outline = (room.x1..room.x2)
+                .flat_map(|x| [Point::new(x, room.y1 - 1), Point::new(x, room.y2)])
+                .chain(
+                    (room.y1..room.y2)
+                        .flat_map(|y| [Point::new(room.x1 - 1, y), Point::new(room.x2, y)]),
+                );
+            for point in outline {
+                let occupied = point == self.player_start
+                    || point == self.amulet_start
+                    || self.entity_spawns.contains(&point)
+                    || self.vault_spawns.iter().any(|(spawn, _)| *spawn == point);
+                let beside_door = doors
+                    .iter()
+                    .any(|door| DistanceAlg::Pythagoras.distance2d(*door, point) < 1.5);
+                if !occupied && !beside_door && self.is_doorway(point) {
+                    doors.push(point);
+                }
+            }
+        }
+        for door in doors {
+            let idx = self.map.map_idx(door);
+            self.map.tiles[idx] = TileType::DoorClosed;
+        }
+    }
+
+    /// Grows a pool of shallow water from `center` with a short random walk over the floor and
+    /// deepens every tile of it that is surrounded by water on all sides.
+    fn flood_pool(&self, center: Point, rng: &mut RandomNumberGenerator) -> Map {
+        let mut map = self.map.clone();
+        let mut pos = center;
+        for _ in 0..POOL_STEPS {
+            let idx = map.map_idx(pos);
+            if map.tiles[idx] == TileType::Floor
+                && pos != self.player_start
+                && pos != self.amulet_start
+            {
+                map.tiles[idx] = TileType::ShallowWater;
+            }
+            let neighbors: Vec<Point> = map.orthogonal_neighbors(pos).collect();
+            let next = neighbors[rng.random_slice_index(&neighbors).unwrap()];
+            if map.tiles[map.map_idx(next)] != TileType::Wall {
+                pos = next;
+            }
+        }
+        let deep: Vec<usize> = (0..map.tiles.len())
+            .filter(|idx| map.tiles[*idx] == TileType::ShallowWater)
+            .filter(|idx| {
+                let point = map.map_point(*idx);
+                map.neighbors(point).count() == 8
+                    && map
+                        .neighbors(point)
+                        .all(|n| map.tiles[map.map_idx(n)] == TileType::ShallowWater)
+            })
+            .collect();
+        deep.iter()
+            .for_each(|idx| map.tiles[*idx] = TileType::DeepWater);
+        map
+    }
+
+    fn add_terrain(&mut self, rng: &mut RandomNumberGenerator) {
+        let reachable = |map: &Map| {
+            DijkstraMap::new(
+                map.width,
+                map.height,
+                &[map.map_idx(self.player_start)],
+                &map.for_door_openers(),
+                PATHFINDING_MAX_DEPTH,
+            )
+        };
+        let before = reachable(&self.map);
+        let floor: Vec<Point> = (0..self.map.tiles.len())
+            .filter(|idx| self.map.tiles[*idx] == TileType::Floor && before.map[*idx] < f32::MAX)
+            .map(|idx| self.map.map_point(idx))
+            .collect();
+        if floor.is_empty() {
+            return;
+        }
+
+        for _ in 0..NUM_POOLS {
+            let center = *rng.random_slice_entry(&floor).unwrap();
+            let mut map = self.flood_pool(center, rng);
+            let after = reachable(&map);
+            let cut_off = (0..map.tiles.len()).any(|idx| {
+                map.tiles[idx].is_passable()
+                    && before.map[idx] < f32::MAX
+                    && after.map[idx] == f32::MAX
+            });
+            if cut_off {
+                map.tiles
+                    .iter_mut()
+                    .zip(self.map.tiles.iter())
+                    .filter(|(tile, old)| {
+                        **tile == TileType::DeepWater && **old != TileType::DeepWater
+                    })
+                    .for_each(|(tile, _)| *tile = TileType::ShallowWater);
+            }
+            self.map = map;
+        }
+
+        let rubble: Vec<usize> = (0..self.map.tiles.len())
+            .filter(|idx| self.map.tiles[*idx] == TileType::Floor)
+            .filter(|idx| {
+                let point = self.map.map_point(*idx);
+                point != self.player_start
+                    && point != self.amulet_start
+                    && self
+                        .map
+                        .neighbors(point)
+                        .filter(|n| self.map.tiles[self.map.map_idx(*n)] == TileType::Wall)
+                        .count()
+                        >= 4
+            })
+            .collect();
+        for idx in rubble {
+            if rng.range(0, 100) < RUBBLE_CHANCE {
+                self.map.tiles[idx] = TileType::Rubble;
+            }
+        }
+    }
+
     fn fill_unreachable(&mut self, from: Point) {
         let dijkstra_map = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            self.map.width,
+            self.map.height,
             &[self.map.point2d_to_index(from)],
-            &self.map,
+            &self.map.for_door_openers(),
             1024.0,
         );
         const UNREACHABLE: &f32 = &f32::MAX;
@@ -154,21 +397,31 @@ impl MapBuilder {
             .for_each(|(idx, _)| self.map.tiles[idx] = TileType::Wall);
     }
 
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
-        let mut architect: Box<dyn MapArchitect> = match rng.range(0, 3) {
-            0 => Box::new(DrunkardsWalkArchitect {}),
-            1 => Box::new(RoomsArchitect {}),
-            _ => Box::new(CellularAutomataArchitect {}),
-        };
-        let mut mb = architect.new(rng);
-        mb.map.update_revealability();
-        mb.map.update_dijkstra_maps();
-        mb
+    pub fn new(
+        rng: &mut RandomNumberGenerator,
+        architects: &ArchitectRegistry,
+        vaults: &VaultLibrary,
+        level: usize,
+    ) -> Result<Self, String> {
+        let mut last_tried = "";
+        for _ in 0..MAX_BUILD_ATTEMPTS {
+            let pipeline = architects.pick(rng, level);
+            if let Some(mut mb) = pipeline.build(rng, vaults, level) {
+                mb.map.update_revealability();
+                return Ok(mb);
+            }
+            last_tried = &pipeline.name;
+        }
+        Err(format!(
+            "[{}] No map built for level {} in {} attempts",
+            last_tried, level, MAX_BUILD_ATTEMPTS
+        ))
     }
 }
 
 pub trait MapTheme: Sync + Send {
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
+    fn kind(&self) -> ThemeKind;
 }
 
 pub fn random_theme(rng: &mut RandomNumberGenerator) -> Box<dyn MapTheme> {
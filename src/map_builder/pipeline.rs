@@ -0,0 +1,112 @@
+use super::{map_dimensions, ArchitectKind, MapBuilder, VaultLibrary};
+use crate::prelude::*;
+use serde::Deserialize;
+
+/// One step run on a map after its architect has laid it out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum BuildStep {
+    /// Walls off every floor tile that cannot be reached from the player start.
+    CullUnreachable,
+    /// Smooths the map with the given number of cellular automata passes.
+    Erode(usize),
+    /// Moves the player start to the floor tile closest to the middle of the map, if there is one
+    /// with somewhere to go.
+    CentralStart,
+    /// Scatters monster spawns over the floor, away from the player start.
+    Spawns,
+    /// Hands out monster spawns one room at a time, skipping the room the player starts in.
+    RoomSpawns,
+    /// Puts the exit, or the amulet on the last level, as far from the player as possible.
+    Exit,
+    /// Stamps vaults from the vault library onto the map; a vault may move the exit.
+    Vaults,
+    /// Puts a closed door in each doorway where a corridor enters a room.
+    Doors,
+    /// Floods a few patches of floor with shallow water around deep pools and scatters rubble
+    /// along the walls, without cutting anything off from the player start.
+    Terrain,
+}
+
+impl BuildStep {
+    fn apply(
+        &self,
+        mb: &mut MapBuilder,
+        rng: &mut RandomNumberGenerator,
+        vaults: &VaultLibrary,
+        level: usize,
+    ) {
+        match self {
+            BuildStep::CullUnreachable => mb.fill_unreachable(mb.player_start),
+            BuildStep::Erode(passes) => {
+                for _ in 0..*passes {
+                    mb.erode();
+                }
+            }
+            BuildStep::CentralStart => {
+                if let Some(start) = mb.find_central_start() {
+                    mb.player_start = start;
+                }
+            }
+            BuildStep::Spawns => mb.entity_spawns = mb.spawn_monsters(mb.player_start, rng),
+            BuildStep::RoomSpawns => mb.entity_spawns = mb.spawn_in_rooms(rng),
+            BuildStep::Exit => mb.amulet_start = mb.find_most_distant(),
+            BuildStep::Vaults => vaults.apply(mb, rng, level),
+            BuildStep::Doors => mb.place_doors(),
+            BuildStep::Terrain => mb.add_terrain(rng),
+        }
+    }
+}
+
+/// An architect followed by the steps that finish its maps, and how likely the pipeline is to
+/// build each level. The last weight also covers every deeper level.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Pipeline {
+    pub name: String,
+    architect: ArchitectKind,
+    steps: Vec<BuildStep>,
+    weights: Vec<u32>,
+}
+
+impl Pipeline {
+    pub fn weight(&self, level: usize) -> u32 {
+        self.weights
+            .get(level)
+            .or_else(|| self.weights.last())
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub(super) fn validate(&self, problems: &mut Vec<String>) {
+        if self.weights.is_empty() {
+            problems.push(format!("[{}] No weights", self.name));
+        }
+        match self
+            .steps
+            .iter()
+            .filter(|step| **step == BuildStep::Exit)
+            .count()
+        {
+            0 => problems.push(format!("[{}] No Exit step", self.name)),
+            1 => {}
+            _ => problems.push(format!("[{}] More than one Exit step", self.name)),
+        }
+    }
+
+    /// Builds a map for the level, or returns `None` if the architect could not lay one out.
+    pub fn build(
+        &self,
+        rng: &mut RandomNumberGenerator,
+        vaults: &VaultLibrary,
+        level: usize,
+    ) -> Option<MapBuilder> {
+        let dimensions = map_dimensions(level);
+        let mut mb = self
+            .architect
+            .architect()
+            .new(rng, dimensions.x, dimensions.y)?;
+        self.steps
+            .iter()
+            .for_each(|step| step.apply(&mut mb, rng, vaults, level));
+        Some(mb)
+    }
+}
@@ -1,82 +1,303 @@
 use crate::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
 
-const FORTRESS: (&str, i32, i32) = (
-    "
-------------
----######---
----#----#---
----#-M--#---
--###----###-
---M------M--
--###----###-
----#----#---
----#----#---
----######---
-------------
-",
-    12,
-    11,
-);
-
-pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
-    let mut placement = None;
-
-    let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
-        &[mb.map.point2d_to_index(mb.player_start)],
-        &mb.map,
-        1024.0,
-    );
-
-    let mut attempts = 0;
-    while placement.is_none() && attempts < 10 {
-        let dimensions = Rect::with_size(
-            rng.range(1, SCREEN_WIDTH - FORTRESS.1 - 1),
-            rng.range(1, SCREEN_HEIGHT - FORTRESS.2 - 1),
-            FORTRESS.1,
-            FORTRESS.2,
+pub const VAULT_FILE: &str = "resources/vaults.ron";
+const MAX_VAULTS_PER_LEVEL: usize = 3;
+const PLACEMENT_ATTEMPTS: usize = 10;
+const MIN_START_DISTANCE: f32 = 20.0;
+
+/// What a character in a vault layout turns into.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+enum VaultTile {
+    Wall,
+    Floor,
+    /// A floor tile where the level's usual random spawns may place something.
+    Spawn,
+    /// A floor tile that always gets the named enemy template.
+    Monster(String),
+    /// A floor tile that always gets the named item template.
+    Item(String),
+    /// A floor tile that becomes the level's exit, or holds the amulet on the last level.
+    Exit,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Vault {
+    name: String,
+    min_depth: usize,
+    /// The vault is considered for one level in `rarity`.
+    rarity: u32,
+    legend: HashMap<char, VaultTile>,
+    layout: String,
+}
+
+impl Vault {
+    fn rows(&self) -> Vec<Vec<char>> {
+        self.layout
+            .lines()
+            .map(|row| row.trim_end_matches('\r'))
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars().collect())
+            .collect()
+    }
+
+    fn tiles(&self) -> impl Iterator<Item = (Point, Option<&VaultTile>)> {
+        self.rows()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.into_iter()
+                    .enumerate()
+                    .map(move |(x, c)| (Point::new(x as i32, y as i32), c))
+            })
+            .map(|(point, c)| (point, self.legend.get(&c)))
+    }
+
+    fn size(&self) -> Point {
+        let rows = self.rows();
+        Point::new(
+            rows.first().map_or(0, |row| row.len()) as i32,
+            rows.len() as i32,
+        )
+    }
+
+    fn has_exit(&self) -> bool {
+        self.legend.values().any(|tile| *tile == VaultTile::Exit)
+    }
+
+    fn validate(&self, problems: &mut Vec<String>) {
+        let name = &self.name;
+        if self.rarity == 0 {
+            problems.push(format!("[{}] rarity must be at least 1", name));
+        }
+        if self.min_depth >= NUM_LEVELS {
+            problems.push(format!(
+                "[{}] min_depth {} is deeper than the {} levels of the dungeon",
+                name, self.min_depth, NUM_LEVELS
+            ));
+        }
+        let rows = self.rows();
+        let size = self.size();
+        if rows.is_empty() {
+            problems.push(format!("[{}] Layout is empty", name));
+        }
+        if rows.iter().any(|row| row.len() as i32 != size.x) {
+            problems.push(format!("[{}] Layout rows differ in length", name));
+        }
+        let mut unknown: Vec<char> = rows
+            .iter()
+            .flatten()
+            .filter(|c| !self.legend.contains_key(c))
+            .copied()
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        if !unknown.is_empty() {
+            problems.push(format!(
+                "[{}] Layout uses {:?} without a legend",
+                name, unknown
+            ));
+        }
+        let exits = self
+            .tiles()
+            .filter(|(_, tile)| *tile == Some(&VaultTile::Exit))
+            .count();
+        if exits > 1 {
+            problems.push(format!("[{}] Layout has {} exits, at most 1", name, exits));
+        }
+    }
+
+    /// Tries a few random spots away from the player and stamps the vault on the first one
+    /// where every tile that could be reached before, the vault itself and the amulet or exit
+    /// can all still be reached from the player start. Returns the area it was placed in.
+    fn place(
+        &self,
+        mb: &mut MapBuilder,
+        rng: &mut RandomNumberGenerator,
+        placed: &[Rect],
+    ) -> Option<Rect> {
+        let size = self.size();
+        if size.x + 2 >= mb.map.width || size.y + 2 >= mb.map.height {
+            return None;
+        }
+        let before = DijkstraMap::new(
+            mb.map.width,
+            mb.map.height,
+            &[mb.map.map_idx(mb.player_start)],
+            &mb.map.for_door_openers(),
+            1024.0,
         );
-        let mut can_place = false;
-        dimensions.for_each(|pt| {
-            let idx = mb.map.point2d_to_index(pt);
-            let distance = dijkstra_map.map[idx];
-            if distance < 2000.0 && distance > 20.0 && mb.amulet_start != pt {
-                can_place = true;
+
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            let area = Rect::with_size(
+                rng.range(1, mb.map.width - size.x - 1),
+                rng.range(1, mb.map.height - size.y - 1),
+                size.x,
+                size.y,
+            );
+            if area.point_in_rect(mb.player_start)
+                || area.point_in_rect(mb.amulet_start)
+                || placed.iter().any(|other| other.intersect(&area))
+            {
+                continue;
+            }
+            let mut away_from_start = false;
+            area.for_each(|point| {
+                let distance = before.map[mb.map.map_idx(point)];
+                if distance > MIN_START_DISTANCE && distance < f32::MAX {
+                    away_from_start = true;
+                }
+            });
+            if !away_from_start {
+                continue;
+            }
+
+            let origin = Point::new(area.x1, area.y1);
+            let mut map = mb.map.clone();
+            let mut exit = None;
+            for (offset, tile) in self.tiles() {
+                let point = origin + offset;
+                let idx = map.map_idx(point);
+                map.tiles[idx] = match tile {
+                    Some(VaultTile::Wall) => TileType::Wall,
+                    _ => TileType::Floor,
+                };
+                if tile == Some(&VaultTile::Exit) {
+                    exit = Some(point);
+                }
+            }
+            let goal = exit.unwrap_or(mb.amulet_start);
+
+            let after = DijkstraMap::new(
+                map.width,
+                map.height,
+                &[map.map_idx(mb.player_start)],
+                &map.for_door_openers(),
+                1024.0,
+            );
+            let connected = (0..map.tiles.len()).all(|idx| {
+                let point = map.map_point(idx);
+                let must_reach = if area.point_in_rect(point) {
+                    map.tiles[idx] != TileType::Wall
+                } else {
+                    before.map[idx] < f32::MAX || point == goal
+                };
+                !must_reach || after.map[idx] < f32::MAX
+            });
+            if !connected {
+                continue;
+            }
+
+            mb.map = map;
+            mb.entity_spawns.retain(|point| !area.point_in_rect(*point));
+            for (offset, tile) in self.tiles() {
+                let point = origin + offset;
+                match tile {
+                    Some(VaultTile::Spawn) => mb.entity_spawns.push(point),
+                    Some(VaultTile::Monster(name)) | Some(VaultTile::Item(name)) => {
+                        mb.vault_spawns.push((point, name.clone()))
+                    }
+                    _ => {}
+                }
             }
-        });
-        if can_place {
-            placement = Some(Point::new(dimensions.x1, dimensions.y1));
-            let points = dimensions.point_set();
-            mb.entity_spawns.retain(|pt| !points.contains(pt));
-            break;
+            mb.amulet_start = goal;
+            return Some(area);
         }
-        attempts += 1;
+        None
     }
+}
 
-    if let Some(placement) = placement {
-        let string_vec: Vec<char> = FORTRESS
-            .0
-            .chars()
-            .filter(|a| *a != '\r' && *a != '\n')
-            .collect();
-        let mut i = 0;
-        for ty in placement.y..placement.y + FORTRESS.2 {
-            for tx in placement.x..placement.x + FORTRESS.1 {
-                let point = Point::new(tx, ty);
-                let idx = Map::map_idx(point);
-                let c = string_vec[i];
-
-                match c {
-                    'M' => {
-                        mb.map.tiles[idx] = TileType::Floor;
-                        mb.entity_spawns.push(point);
-                    }
-                    '-' => mb.map.tiles[idx] = TileType::Floor,
-                    '#' => mb.map.tiles[idx] = TileType::Wall,
-                    _ => println!("No idea what to do with [{}]", c),
+/// Hand-made rooms read from `resources/vaults.ron` and stamped onto levels by the `Vaults`
+/// pipeline step.
+#[derive(Clone, Debug, Deserialize)]
+pub struct VaultLibrary {
+    vaults: Vec<Vault>,
+}
+
+impl VaultLibrary {
+    pub fn load() -> Result<Self, String> {
+        let source = std::fs::read_to_string(VAULT_FILE)
+            .map_err(|err| format!("Unable to read {}: {}", VAULT_FILE, err))?;
+        let library: Self = ron::de::from_str(&source)
+            .map_err(|err| format!("{}:{}: {}", VAULT_FILE, err.position, err.code))?;
+        let problems = library.validate();
+        if problems.is_empty() {
+            Ok(library)
+        } else {
+            Err(format!("{}: {}", VAULT_FILE, problems.join("; ")))
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (idx, vault) in self.vaults.iter().enumerate() {
+            if self.vaults[..idx]
+                .iter()
+                .any(|other| other.name == vault.name)
+            {
+                problems.push(format!("[{}] Duplicate vault name", vault.name));
+            }
+            vault.validate(&mut problems);
+        }
+        problems
+    }
+
+    /// Checks that every monster and item a vault names is a template of the right kind.
+    pub fn check_templates(&self, templates: &Templates) -> Result<(), String> {
+        let problems = self.template_problems(templates);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("{}: {}", VAULT_FILE, problems.join("; ")))
+        }
+    }
+
+    /// Every monster or item a vault names that has no template of that type.
+    pub fn template_problems(&self, templates: &Templates) -> Vec<String> {
+        let mut problems = Vec::new();
+        for vault in self.vaults.iter() {
+            let mut named: Vec<(&String, EntityType)> = vault
+                .legend
+                .values()
+                .filter_map(|tile| match tile {
+                    VaultTile::Monster(name) => Some((name, EntityType::Enemy)),
+                    VaultTile::Item(name) => Some((name, EntityType::Item)),
+                    _ => None,
+                })
+                .collect();
+            named.sort_by_key(|(name, _)| *name);
+            for (name, entity_type) in named {
+                match templates.entities.iter().find(|t| t.name == *name) {
+                    None => problems.push(format!("[{}] No template named {}", vault.name, name)),
+                    Some(template) if template.entity_type != entity_type => problems.push(
+                        format!("[{}] {} is not an {:?}", vault.name, name, entity_type),
+                    ),
+                    _ => {}
                 }
-                i += 1;
+            }
+        }
+        problems
+    }
+
+    /// Picks vaults allowed at this depth in random order, skipping each with its rarity, and
+    /// places up to `MAX_VAULTS_PER_LEVEL` of them. Only one placed vault may hold the exit.
+    pub fn apply(&self, mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, level: usize) {
+        let mut candidates: Vec<&Vault> = self
+            .vaults
+            .iter()
+            .filter(|vault| vault.min_depth <= level)
+            .collect();
+        let mut placed = Vec::new();
+        let mut exit_placed = false;
+        while !candidates.is_empty() && placed.len() < MAX_VAULTS_PER_LEVEL {
+            let vault = candidates.remove(rng.random_slice_index(&candidates).unwrap());
+            if rng.range(0, vault.rarity) != 0 || (exit_placed && vault.has_exit()) {
+                continue;
+            }
+            if let Some(area) = vault.place(mb, rng, &placed) {
+                exit_placed |= vault.has_exit();
+                placed.push(area);
             }
         }
     }
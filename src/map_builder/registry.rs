@@ -0,0 +1,119 @@
+use super::automata::CellularAutomataArchitect;
+use super::bsp::BspArchitect;
+use super::drunkard::DrunkardsWalkArchitect;
+use super::empty::EmptyArchitect;
+use super::pipeline::Pipeline;
+use super::rooms::RoomsArchitect;
+use super::wfc::WaveFunctionCollapseArchitect;
+use super::MapArchitect;
+use crate::prelude::*;
+use serde::Deserialize;
+
+const ARCHITECT_FILE: &str = "resources/architects.ron";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ArchitectKind {
+    Empty,
+    Rooms,
+    CellularAutomata,
+    DrunkardsWalk,
+    Bsp,
+    WaveFunctionCollapse,
+}
+
+impl ArchitectKind {
+    pub(super) fn architect(&self) -> Box<dyn MapArchitect> {
+        match self {
+            ArchitectKind::Empty => Box::new(EmptyArchitect {}),
+            ArchitectKind::Rooms => Box::new(RoomsArchitect {}),
+            ArchitectKind::CellularAutomata => Box::new(CellularAutomataArchitect {}),
+            ArchitectKind::DrunkardsWalk => Box::new(DrunkardsWalkArchitect {}),
+            ArchitectKind::Bsp => Box::new(BspArchitect {}),
+            ArchitectKind::WaveFunctionCollapse => Box::new(WaveFunctionCollapseArchitect {}),
+        }
+    }
+}
+
+/// The pipelines that may build each level and how likely each one is, read from
+/// `resources/architects.ron`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ArchitectRegistry {
+    pipelines: Vec<Pipeline>,
+    #[serde(skip)]
+    forced: Option<usize>,
+}
+
+impl ArchitectRegistry {
+    pub fn load() -> Result<Self, String> {
+        let source = std::fs::read_to_string(ARCHITECT_FILE)
+            .map_err(|err| format!("Unable to read {}: {}", ARCHITECT_FILE, err))?;
+        let registry: Self = ron::de::from_str(&source)
+            .map_err(|err| format!("{}:{}: {}", ARCHITECT_FILE, err.position, err.code))?;
+        let problems = registry.validate();
+        if problems.is_empty() {
+            Ok(registry)
+        } else {
+            Err(format!("{}: {}", ARCHITECT_FILE, problems.join("; ")))
+        }
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (idx, pipeline) in self.pipelines.iter().enumerate() {
+            if self.pipelines[..idx]
+                .iter()
+                .any(|other| other.name == pipeline.name)
+            {
+                problems.push(format!("[{}] Listed twice", pipeline.name));
+            }
+            pipeline.validate(&mut problems);
+        }
+        for level in 0..NUM_LEVELS {
+            if self.total_weight(level) == 0 {
+                problems.push(format!("[level {}] No pipeline can build it", level));
+            }
+        }
+        problems
+    }
+
+    pub fn pipelines(&self) -> &[Pipeline] {
+        &self.pipelines
+    }
+
+    /// Makes every level use the named pipeline, regardless of the configured weights.
+    pub fn force(&mut self, name: &str) -> Result<(), String> {
+        match self.pipelines.iter().position(|p| p.name == name) {
+            Some(idx) => {
+                self.forced = Some(idx);
+                Ok(())
+            }
+            None => {
+                let names: Vec<&str> = self.pipelines.iter().map(|p| p.name.as_str()).collect();
+                Err(format!(
+                    "Unknown architect [{}], expected one of {}",
+                    name,
+                    names.join(", ")
+                ))
+            }
+        }
+    }
+
+    fn total_weight(&self, level: usize) -> u32 {
+        self.pipelines.iter().map(|p| p.weight(level)).sum()
+    }
+
+    pub fn pick(&self, rng: &mut RandomNumberGenerator, level: usize) -> &Pipeline {
+        if let Some(idx) = self.forced {
+            return &self.pipelines[idx];
+        }
+        let mut roll = rng.range(0, self.total_weight(level));
+        for pipeline in self.pipelines.iter() {
+            let weight = pipeline.weight(level);
+            if roll < weight {
+                return pipeline;
+            }
+            roll -= weight;
+        }
+        unreachable!("Pipeline weights were validated when the registry was loaded")
+    }
+}
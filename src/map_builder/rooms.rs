@@ -4,11 +4,17 @@ use crate::prelude::*;
 pub struct RoomsArchitect {}
 
 impl MapArchitect for RoomsArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> Option<MapBuilder> {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(width, height),
             rooms: Vec::new(),
             entity_spawns: Vec::new(),
+            vault_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
             theme: random_theme(rng),
@@ -17,9 +23,6 @@ impl MapArchitect for RoomsArchitect {
         mb.build_random_rooms(rng);
         mb.build_corridors(rng);
         mb.player_start = mb.rooms[0].center();
-        prefab::apply_prefab(&mut mb, rng);
-        mb.amulet_start = mb.find_most_distant();
-        mb.entity_spawns = mb.spawn_monsters(mb.player_start, rng);
-        mb
+        Some(mb)
     }
 }
@@ -1,8 +1,25 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    Dungeon,
+    Forest,
+}
+
+impl ThemeKind {
+    pub fn theme(&self) -> Box<dyn MapTheme> {
+        match self {
+            ThemeKind::Dungeon => DungeonTheme::new(),
+            ThemeKind::Forest => ForestTheme::new(),
+        }
+    }
+}
 
 pub struct DungeonTheme {}
 
 impl DungeonTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
@@ -14,13 +31,24 @@ impl MapTheme for DungeonTheme {
             TileType::Floor => to_cp437('.'),
             TileType::Wall => to_cp437('#'),
             TileType::Exit => to_cp437('>'),
+            TileType::UpStairs => to_cp437('<'),
+            TileType::DoorClosed => to_cp437('+'),
+            TileType::DoorOpen => to_cp437('\''),
+            TileType::ShallowWater => to_cp437('~'),
+            TileType::Rubble => to_cp437(':'),
+            TileType::DeepWater => to_cp437('≈'),
         }
     }
+
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Dungeon
+    }
 }
 
 pub struct ForestTheme {}
 
 impl ForestTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
@@ -32,6 +60,16 @@ impl MapTheme for ForestTheme {
             TileType::Floor => to_cp437(';'),
             TileType::Wall => to_cp437('"'),
             TileType::Exit => to_cp437('>'),
+            TileType::UpStairs => to_cp437('<'),
+            TileType::DoorClosed => to_cp437('='),
+            TileType::DoorOpen => to_cp437('_'),
+            TileType::ShallowWater => to_cp437('~'),
+            TileType::Rubble => to_cp437('%'),
+            TileType::DeepWater => to_cp437('≈'),
         }
     }
+
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Forest
+    }
 }
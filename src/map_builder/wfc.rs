@@ -0,0 +1,383 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+const CHUNK_SIZE: i32 = 5;
+const MAX_ATTEMPTS: usize = 10;
+const MIN_FLOOR_FRACTION: usize = 5;
+
+/// Example layouts in the same style as the vaults in `resources/vaults.ron`: `#` is a wall and
+/// `-` a floor. They are cut into `CHUNK_SIZE` squares, and every rotation and mirror image of
+/// those squares may be placed next to any other whose touching edge matches.
+const EXAMPLES: [(&str, i32, i32); 2] = [
+    (
+        "
+###############
+#-------------#
+#-------------#
+#-------------#
+#-------------#
+#-------------#
+#-------------#
+---------------
+#-------------#
+#-------------#
+#-------------#
+#-------------#
+#-------------#
+#-------------#
+#######-#######
+",
+        15,
+        15,
+    ),
+    (
+        "
+#######-#######
+#######-#######
+#######-#######
+#######-#######
+#######-#######
+#######-#######
+#######-#######
+-------------##
+#######-####-##
+#######-####-##
+#######-####-##
+#######-####-##
+#######------##
+###############
+###############
+",
+        15,
+        15,
+    ),
+];
+
+type Chunk = Vec<TileType>;
+
+#[derive(Clone, Copy)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+impl Direction {
+    fn offset(&self) -> Point {
+        match self {
+            Direction::North => Point::new(0, -1),
+            Direction::East => Point::new(1, 0),
+            Direction::South => Point::new(0, 1),
+            Direction::West => Point::new(-1, 0),
+        }
+    }
+
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+fn chunk_tile(chunk: &Chunk, x: i32, y: i32) -> TileType {
+    chunk[(y * CHUNK_SIZE + x) as usize]
+}
+
+fn edge(chunk: &Chunk, direction: Direction) -> Vec<TileType> {
+    (0..CHUNK_SIZE)
+        .map(|i| match direction {
+            Direction::North => chunk_tile(chunk, i, 0),
+            Direction::East => chunk_tile(chunk, CHUNK_SIZE - 1, i),
+            Direction::South => chunk_tile(chunk, i, CHUNK_SIZE - 1),
+            Direction::West => chunk_tile(chunk, 0, i),
+        })
+        .collect()
+}
+
+fn transformed(chunk: &Chunk, transform: impl Fn(i32, i32) -> (i32, i32)) -> Chunk {
+    (0..CHUNK_SIZE * CHUNK_SIZE)
+        .map(|idx| {
+            let (x, y) = transform(idx % CHUNK_SIZE, idx / CHUNK_SIZE);
+            chunk_tile(chunk, x, y)
+        })
+        .collect()
+}
+
+/// Every distinct chunk in the examples, including rotations and mirror images, with how often
+/// each one occurs.
+fn example_chunks() -> Vec<(Chunk, u32)> {
+    let last = CHUNK_SIZE - 1;
+    let mut chunks: Vec<(Chunk, u32)> = Vec::new();
+    for (example, width, height) in EXAMPLES {
+        let tiles: Vec<TileType> = example
+            .chars()
+            .filter(|c| *c != '\r' && *c != '\n')
+            .map(|c| match c {
+                '-' => TileType::Floor,
+                _ => TileType::Wall,
+            })
+            .collect();
+        for chunk_y in 0..height / CHUNK_SIZE {
+            for chunk_x in 0..width / CHUNK_SIZE {
+                let chunk: Chunk = (0..CHUNK_SIZE * CHUNK_SIZE)
+                    .map(|idx| {
+                        let x = chunk_x * CHUNK_SIZE + idx % CHUNK_SIZE;
+                        let y = chunk_y * CHUNK_SIZE + idx / CHUNK_SIZE;
+                        tiles[(y * width + x) as usize]
+                    })
+                    .collect();
+                let mirrored = transformed(&chunk, |x, y| (last - x, y));
+                let mut variants = Vec::new();
+                for base in [chunk, mirrored] {
+                    let mut rotated = base;
+                    for _ in 0..4 {
+                        let next = transformed(&rotated, |x, y| (y, last - x));
+                        variants.push(rotated);
+                        rotated = next;
+                    }
+                }
+                for variant in variants {
+                    match chunks.iter_mut().find(|(chunk, _)| *chunk == variant) {
+                        Some((_, count)) => *count += 1,
+                        None => chunks.push((variant, 1)),
+                    }
+                }
+            }
+        }
+    }
+    chunks
+}
+
+/// Collapses a grid of chunk slots one slot at a time, always picking the slot with the fewest
+/// remaining options, and removes options from the neighbours whose edges no longer match.
+struct Solver {
+    chunks: Vec<(Chunk, u32)>,
+    compatible: Vec<[Vec<bool>; 4]>,
+    grid_width: i32,
+    grid_height: i32,
+    options: Vec<Vec<bool>>,
+}
+
+impl Solver {
+    fn new(grid_width: i32, grid_height: i32) -> Self {
+        let chunks = example_chunks();
+        let compatible = chunks
+            .iter()
+            .map(|(chunk, _)| {
+                DIRECTIONS.map(|direction| {
+                    chunks
+                        .iter()
+                        .map(|(other, _)| {
+                            edge(chunk, direction) == edge(other, direction.opposite())
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+        let options = vec![vec![true; chunks.len()]; (grid_width * grid_height) as usize];
+        Self {
+            chunks,
+            compatible,
+            grid_width,
+            grid_height,
+            options,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.options
+            .iter_mut()
+            .for_each(|options| options.iter_mut().for_each(|option| *option = true));
+    }
+
+    fn remaining(&self, cell: usize) -> usize {
+        self.options[cell].iter().filter(|option| **option).count()
+    }
+
+    /// Returns false if some slot ran out of options.
+    fn solve(&mut self, rng: &mut RandomNumberGenerator) -> bool {
+        loop {
+            let undecided = (0..self.options.len())
+                .map(|cell| (cell, self.remaining(cell)))
+                .filter(|(_, remaining)| *remaining > 1)
+                .min_by_key(|(_, remaining)| *remaining);
+            let Some((cell, _)) = undecided else {
+                return true;
+            };
+
+            let candidates: Vec<(usize, u32)> = self.options[cell]
+                .iter()
+                .enumerate()
+                .filter(|(_, option)| **option)
+                .map(|(chunk, _)| (chunk, self.chunks[chunk].1))
+                .collect();
+            let total: u32 = candidates.iter().map(|(_, weight)| weight).sum();
+            let mut roll = rng.range(0, total);
+            let chosen = candidates
+                .iter()
+                .find(|(_, weight)| {
+                    if roll < *weight {
+                        true
+                    } else {
+                        roll -= weight;
+                        false
+                    }
+                })
+                .map(|(chunk, _)| *chunk)
+                .unwrap();
+            self.options[cell]
+                .iter_mut()
+                .enumerate()
+                .for_each(|(chunk, option)| *option = chunk == chosen);
+
+            if !self.propagate(cell) {
+                return false;
+            }
+        }
+    }
+
+    fn propagate(&mut self, start: usize) -> bool {
+        let mut pending = vec![start];
+        while let Some(cell) = pending.pop() {
+            let position = Point::new(cell as i32 % self.grid_width, cell as i32 / self.grid_width);
+            for (dir_idx, direction) in DIRECTIONS.iter().enumerate() {
+                let neighbor = position + direction.offset();
+                if neighbor.x < 0
+                    || neighbor.y < 0
+                    || neighbor.x >= self.grid_width
+                    || neighbor.y >= self.grid_height
+                {
+                    continue;
+                }
+                let neighbor_cell = (neighbor.y * self.grid_width + neighbor.x) as usize;
+                let mut changed = false;
+                for other in 0..self.chunks.len() {
+                    if !self.options[neighbor_cell][other] {
+                        continue;
+                    }
+                    let supported = self.options[cell]
+                        .iter()
+                        .enumerate()
+                        .any(|(chunk, option)| *option && self.compatible[chunk][dir_idx][other]);
+                    if !supported {
+                        self.options[neighbor_cell][other] = false;
+                        changed = true;
+                    }
+                }
+                if changed {
+                    if self.remaining(neighbor_cell) == 0 {
+                        return false;
+                    }
+                    pending.push(neighbor_cell);
+                }
+            }
+        }
+        true
+    }
+
+    fn write_to(&self, map: &mut Map) {
+        for (cell, options) in self.options.iter().enumerate() {
+            let Some(chunk) = options.iter().position(|option| *option) else {
+                continue;
+            };
+            let origin = Point::new(
+                1 + (cell as i32 % self.grid_width) * CHUNK_SIZE,
+                1 + (cell as i32 / self.grid_width) * CHUNK_SIZE,
+            );
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let idx = map.map_idx(origin + Point::new(x, y));
+                    map.tiles[idx] = chunk_tile(&self.chunks[chunk].0, x, y);
+                }
+            }
+        }
+    }
+}
+
+pub struct WaveFunctionCollapseArchitect {}
+
+impl MapArchitect for WaveFunctionCollapseArchitect {
+    fn new(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> Option<MapBuilder> {
+        let mut mb = MapBuilder {
+            map: Map::new(width, height),
+            rooms: Vec::new(),
+            entity_spawns: Vec::new(),
+            vault_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            theme: random_theme(rng),
+        };
+        let mut solver = Solver::new((width - 2) / CHUNK_SIZE, (height - 2) / CHUNK_SIZE);
+        for _ in 0..MAX_ATTEMPTS {
+            mb.fill(TileType::Wall);
+            solver.reset();
+            if !solver.solve(rng) {
+                continue;
+            }
+            solver.write_to(&mut mb.map);
+            let Some(start) = mb.find_central_start() else {
+                continue;
+            };
+            mb.fill_unreachable(start);
+            mb.player_start = start;
+            let floor = mb
+                .map
+                .tiles
+                .iter()
+                .filter(|tile| **tile == TileType::Floor)
+                .count();
+            if floor * MIN_FLOOR_FRACTION >= mb.map.tiles.len() {
+                return Some(mb);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_are_connected_and_large_enough() {
+        let mut built = 0;
+        for seed in 0..20 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let Some(mb) =
+                WaveFunctionCollapseArchitect {}.new(&mut rng, SCREEN_WIDTH, SCREEN_HEIGHT)
+            else {
+                continue;
+            };
+            built += 1;
+            let reachable = DijkstraMap::new(
+                mb.map.width,
+                mb.map.height,
+                &[mb.map.map_idx(mb.player_start)],
+                &mb.map,
+                PATHFINDING_MAX_DEPTH,
+            );
+            let floor: Vec<usize> = (0..mb.map.tiles.len())
+                .filter(|idx| mb.map.tiles[*idx] == TileType::Floor)
+                .collect();
+            assert!(floor.len() * MIN_FLOOR_FRACTION >= mb.map.tiles.len());
+            assert!(floor.iter().all(|idx| reachable.map[*idx] < f32::MAX));
+        }
+        assert!(built >= 15, "only {} of 20 seeds built a map", built);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::prelude::*;
+
+pub struct Options {
+    pub seed: u64,
+    pub load: bool,
+    pub headless_turns: Option<usize>,
+    pub benchmark_levels: Option<usize>,
+    pub validate_templates: bool,
+    pub check_architects: Option<usize>,
+    pub architect: Option<String>,
+    pub diagonal_movement: bool,
+}
+
+impl Options {
+    pub fn from_args() -> Result<Self, String> {
+        let mut options = Self {
+            seed: random_seed(),
+            load: false,
+            headless_turns: None,
+            benchmark_levels: None,
+            validate_templates: false,
+            check_architects: None,
+            architect: None,
+            diagonal_movement: false,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => {
+                    let value = args.next().ok_or("--seed expects a value")?;
+                    options.seed = value
+                        .parse()
+                        .map_err(|_| format!("Invalid seed [{}]", value))?;
+                }
+                "--load" => options.load = true,
+                "--validate-templates" => options.validate_templates = true,
+                "--diagonal" => options.diagonal_movement = true,
+                "--headless" => {
+                    let value = args.next().ok_or("--headless expects a number of turns")?;
+                    options.headless_turns = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid number of turns [{}]", value))?,
+                    );
+                }
+                "--benchmark-levels" => {
+                    let value = args
+                        .next()
+                        .ok_or("--benchmark-levels expects a number of levels")?;
+                    options.benchmark_levels = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|levels| *levels > 0)
+                            .ok_or_else(|| format!("Invalid number of levels [{}]", value))?,
+                    );
+                }
+                "--architect" => {
+                    let value = args.next().ok_or("--architect expects an architect name")?;
+                    options.architect = Some(value);
+                }
+                "--check-architects" => {
+                    let value = args
+                        .next()
+                        .ok_or("--check-architects expects a number of runs")?;
+                    options.check_architects = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("Invalid number of runs [{}]", value))?,
+                    );
+                }
+                _ => return Err(format!("Unknown argument [{}]", arg)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+pub fn random_seed() -> u64 {
+    RandomNumberGenerator::new().next_u64()
+}
@@ -0,0 +1,124 @@
+use crate::prelude::*;
+use std::collections::VecDeque;
+
+pub const PATHFINDING_CACHE_SIZE: usize = 64;
+pub const PATHFINDING_MAX_DEPTH: f32 = 1024.0;
+
+/// Least recently used cache of Dijkstra maps, keyed by the tile index they lead to and whether
+/// they lead through closed doors. Maps are only built when a monster first heads for a target,
+/// instead of one per walkable tile.
+pub struct PathfindingCache {
+    capacity: usize,
+    entries: VecDeque<((usize, bool), DijkstraMap)>,
+}
+
+impl PathfindingCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn dijkstra_map(&mut self, map: &Map, target: usize, opens_doors: bool) -> &DijkstraMap {
+        let key = (target, opens_doors);
+        if let Some(position) = self.entries.iter().position(|(entry, _)| *entry == key) {
+            let entry = self.entries.remove(position).unwrap();
+            self.entries.push_front(entry);
+        } else {
+            if self.entries.len() >= self.capacity {
+                self.entries.pop_back();
+            }
+            let dijkstra_map = if opens_doors {
+                let door_opener_map = map.for_door_openers();
+                DijkstraMap::new(
+                    map.width,
+                    map.height,
+                    &[target],
+                    &door_opener_map,
+                    PATHFINDING_MAX_DEPTH,
+                )
+            } else {
+                DijkstraMap::new(map.width, map.height, &[target], map, PATHFINDING_MAX_DEPTH)
+            };
+            self.entries.push_front((key, dijkstra_map));
+        }
+        &self.entries[0].1
+    }
+
+    /// Forgets every cached map, for when the map's tiles change under them.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(_, dijkstra_map)| dijkstra_map.map.len() * std::mem::size_of::<f32>())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A corridor through solid wall with a closed door in the middle.
+    fn corridor() -> Map {
+        let mut map = Map::new(7, 3);
+        map.tiles.fill(TileType::Wall);
+        (1..6).for_each(|x| {
+            let idx = map.map_idx(Point::new(x, 1));
+            map.tiles[idx] = TileType::Floor;
+        });
+        let door = map.map_idx(Point::new(3, 1));
+        map.tiles[door] = TileType::DoorClosed;
+        map
+    }
+
+    fn keys(cache: &PathfindingCache) -> Vec<(usize, bool)> {
+        cache.entries.iter().map(|(key, _)| *key).collect()
+    }
+
+    #[test]
+    fn hits_move_to_the_front_and_the_oldest_is_evicted() {
+        let map = corridor();
+        let mut cache = PathfindingCache::new(2);
+        cache.dijkstra_map(&map, 8, false);
+        cache.dijkstra_map(&map, 9, false);
+        assert_eq!(keys(&cache), vec![(9, false), (8, false)]);
+
+        cache.dijkstra_map(&map, 8, false);
+        assert_eq!(keys(&cache), vec![(8, false), (9, false)]);
+
+        cache.dijkstra_map(&map, 10, false);
+        assert_eq!(keys(&cache), vec![(10, false), (8, false)]);
+    }
+
+    #[test]
+    fn door_openers_get_their_own_maps() {
+        let map = corridor();
+        let target = map.map_idx(Point::new(1, 1));
+        let beyond_door = map.map_idx(Point::new(5, 1));
+        let mut cache = PathfindingCache::new(4);
+
+        let blocked = cache.dijkstra_map(&map, target, false).map[beyond_door];
+        let opened = cache.dijkstra_map(&map, target, true).map[beyond_door];
+        assert_eq!(blocked, f32::MAX);
+        assert_eq!(opened, 4.0);
+        assert_eq!(keys(&cache), vec![(target, true), (target, false)]);
+    }
+
+    #[test]
+    fn clear_forgets_every_map() {
+        let map = corridor();
+        let mut cache = PathfindingCache::new(4);
+        cache.dijkstra_map(&map, 8, false);
+        cache.dijkstra_map(&map, 8, true);
+        assert_eq!(cache.memory_usage(), 2 * 21 * std::mem::size_of::<f32>());
+
+        cache.clear();
+        assert!(keys(&cache).is_empty());
+        assert_eq!(cache.memory_usage(), 0);
+    }
+}
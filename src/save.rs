@@ -0,0 +1,707 @@
+use crate::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+
+pub const SAVE_FILE: &str = "resources/savegame.ron";
+const SAVE_VERSION: u32 = 12;
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveGame {
+    version: u32,
+    pub seed: u64,
+    rng: RandomNumberGenerator,
+    turn_state: TurnState,
+    theme: ThemeKind,
+    game_log: GameLog,
+    map: Map,
+    entities: Vec<SavedEntity>,
+    levels: Vec<SavedLevel>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedLevel {
+    depth: u32,
+    theme: ThemeKind,
+    map: Map,
+    entities: Vec<SavedEntity>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedEntity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    activate_item: Option<(usize, usize)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    amulet_of_yala: Option<AmuletOfYala>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    armor: Option<Armor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    carried_by: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    damage: Option<Damage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    enemy: Option<Enemy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    equippable: Option<Equippable>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    equipped_by: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    field_of_view: Option<FieldOfView>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    health: Option<Health>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hidden: Option<Hidden>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    item: Option<Item>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    player: Option<Player>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    point: Option<Point>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    moving_randomly: Option<MovingRandomly>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<Name>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    opens_doors: Option<OpensDoors>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provides_dungeon_map: Option<ProvidesDungeonMap>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provides_healing: Option<ProvidesHealing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provides_depletion: Option<ProvidesDepletion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provides_destruction_on_level_progress: Option<ProvidesDestructionOnLevelProgress>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provides_equipment: Option<ProvidesEquipment>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    provides_throwing: Option<ProvidesThrowing>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ranged: Option<Ranged>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    regeneration: Option<Regeneration>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    render: Option<Render>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resting: Option<Resting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    roaming_and_chasing_player: Option<RoamingAndChasingPlayer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    trap: Option<Trap>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wants_to_attack: Option<(usize, usize, Option<usize>)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wants_to_shoot: Option<(usize, Point, Option<usize>)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wants_to_move: Option<(usize, Point)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wants_to_search: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wants_to_close_door: Option<(usize, Point)>,
+}
+
+#[derive(Deserialize)]
+struct SaveVersion {
+    version: u32,
+}
+
+fn component<T: Clone + Send + Sync + 'static>(entry: &world::EntryRef) -> Option<T> {
+    entry.get_component::<T>().ok().cloned()
+}
+
+fn add_component<T: Send + Sync + 'static>(entry: &mut world::Entry, component: Option<T>) {
+    if let Some(component) = component {
+        entry.add_component(component);
+    }
+}
+
+fn capture_entities(ecs: &World) -> Vec<SavedEntity> {
+    let entities: Vec<Entity> = <Entity>::query().iter(ecs).copied().collect();
+    let indices: HashMap<Entity, usize> = entities
+        .iter()
+        .enumerate()
+        .map(|(idx, entity)| (*entity, idx))
+        .collect();
+
+    entities
+        .iter()
+        .map(|entity| {
+            let entry = ecs.entry_ref(*entity).unwrap();
+            SavedEntity {
+                activate_item: entry
+                    .get_component::<ActivateItem>()
+                    .ok()
+                    .map(|activate| (indices[&activate.used_by], indices[&activate.item])),
+                amulet_of_yala: component(&entry),
+                armor: component(&entry),
+                carried_by: entry
+                    .get_component::<Carried>()
+                    .ok()
+                    .map(|carried| indices[&carried.by]),
+                damage: component(&entry),
+                enemy: component(&entry),
+                equippable: component(&entry),
+                equipped_by: entry
+                    .get_component::<Equipped>()
+                    .ok()
+                    .map(|equipped| indices[&equipped.by]),
+                field_of_view: entry
+                    .get_component::<FieldOfView>()
+                    .ok()
+                    .map(|fov| fov.clone_dirty()),
+                health: component(&entry),
+                hidden: component(&entry),
+                item: component(&entry),
+                player: component(&entry),
+                point: component(&entry),
+                moving_randomly: component(&entry),
+                name: component(&entry),
+                opens_doors: component(&entry),
+                provides_dungeon_map: component(&entry),
+                provides_healing: component(&entry),
+                provides_depletion: component(&entry),
+                provides_destruction_on_level_progress: component(&entry),
+                provides_equipment: component(&entry),
+                provides_throwing: component(&entry),
+                ranged: component(&entry),
+                regeneration: component(&entry),
+                render: component(&entry),
+                resting: component(&entry),
+                roaming_and_chasing_player: component(&entry),
+                trap: component(&entry),
+                wants_to_attack: entry.get_component::<WantsToAttack>().ok().map(|attack| {
+                    (
+                        indices[&attack.attacker],
+                        indices[&attack.victim],
+                        attack.weapon.map(|weapon| indices[&weapon]),
+                    )
+                }),
+                wants_to_shoot: entry.get_component::<WantsToShoot>().ok().map(|shoot| {
+                    (
+                        indices[&shoot.shooter],
+                        shoot.target,
+                        shoot.weapon.map(|weapon| indices[&weapon]),
+                    )
+                }),
+                wants_to_move: entry
+                    .get_component::<WantsToMove>()
+                    .ok()
+                    .map(|want_move| (indices[&want_move.entity], want_move.destination)),
+                wants_to_search: entry
+                    .get_component::<WantsToSearch>()
+                    .ok()
+                    .map(|search| indices[&search.searcher]),
+                wants_to_close_door: entry
+                    .get_component::<WantsToCloseDoor>()
+                    .ok()
+                    .map(|close| (indices[&close.closer], close.door)),
+            }
+        })
+        .collect()
+}
+
+fn restore_entities(ecs: &mut World, saved_entities: Vec<SavedEntity>) {
+    let entities: Vec<Entity> = saved_entities.iter().map(|_| ecs.push(())).collect();
+
+    for (entity, saved) in entities.iter().zip(saved_entities) {
+        let mut entry = ecs.entry(*entity).unwrap();
+        add_component(
+            &mut entry,
+            saved.activate_item.map(|(used_by, item)| ActivateItem {
+                used_by: entities[used_by],
+                item: entities[item],
+            }),
+        );
+        add_component(&mut entry, saved.amulet_of_yala);
+        add_component(&mut entry, saved.armor);
+        add_component(
+            &mut entry,
+            saved.carried_by.map(|by| Carried { by: entities[by] }),
+        );
+        add_component(&mut entry, saved.damage);
+        add_component(&mut entry, saved.enemy);
+        add_component(&mut entry, saved.equippable);
+        add_component(
+            &mut entry,
+            saved.equipped_by.map(|by| Equipped { by: entities[by] }),
+        );
+        add_component(&mut entry, saved.field_of_view);
+        add_component(&mut entry, saved.health);
+        add_component(&mut entry, saved.hidden);
+        add_component(&mut entry, saved.item);
+        add_component(&mut entry, saved.player);
+        add_component(&mut entry, saved.point);
+        add_component(&mut entry, saved.moving_randomly);
+        add_component(&mut entry, saved.name);
+        add_component(&mut entry, saved.opens_doors);
+        add_component(&mut entry, saved.provides_dungeon_map);
+        add_component(&mut entry, saved.provides_healing);
+        add_component(&mut entry, saved.provides_depletion);
+        add_component(&mut entry, saved.provides_destruction_on_level_progress);
+        add_component(&mut entry, saved.provides_equipment);
+        add_component(&mut entry, saved.provides_throwing);
+        add_component(&mut entry, saved.ranged);
+        add_component(&mut entry, saved.regeneration);
+        add_component(&mut entry, saved.render);
+        add_component(&mut entry, saved.resting);
+        add_component(&mut entry, saved.roaming_and_chasing_player);
+        add_component(&mut entry, saved.trap);
+        add_component(
+            &mut entry,
+            saved
+                .wants_to_attack
+                .map(|(attacker, victim, weapon)| WantsToAttack {
+                    attacker: entities[attacker],
+                    victim: entities[victim],
+                    weapon: weapon.map(|weapon| entities[weapon]),
+                }),
+        );
+        add_component(
+            &mut entry,
+            saved
+                .wants_to_shoot
+                .map(|(shooter, target, weapon)| WantsToShoot {
+                    shooter: entities[shooter],
+                    target,
+                    weapon: weapon.map(|weapon| entities[weapon]),
+                }),
+        );
+        add_component(
+            &mut entry,
+            saved
+                .wants_to_move
+                .map(|(entity, destination)| WantsToMove {
+                    entity: entities[entity],
+                    destination,
+                }),
+        );
+        add_component(
+            &mut entry,
+            saved.wants_to_search.map(|searcher| WantsToSearch {
+                searcher: entities[searcher],
+            }),
+        );
+        add_component(
+            &mut entry,
+            saved
+                .wants_to_close_door
+                .map(|(closer, door)| WantsToCloseDoor {
+                    closer: entities[closer],
+                    door,
+                }),
+        );
+    }
+}
+
+impl SaveGame {
+    pub fn capture(ecs: &World, resources: &Resources, seed: u64) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            seed,
+            rng: resources.get::<RandomNumberGenerator>().unwrap().clone(),
+            turn_state: *resources.get::<TurnState>().unwrap(),
+            theme: resources.get::<Box<dyn MapTheme>>().unwrap().kind(),
+            game_log: resources.get::<GameLog>().unwrap().clone(),
+            map: resources.get::<Map>().unwrap().clone(),
+            entities: capture_entities(ecs),
+            levels: resources
+                .get::<Dungeon>()
+                .unwrap()
+                .levels()
+                .map(|(depth, level)| SavedLevel {
+                    depth,
+                    theme: level.theme,
+                    map: level.map.clone(),
+                    entities: capture_entities(&level.ecs),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn restore(self, ecs: &mut World, resources: &mut Resources) {
+        restore_entities(ecs, self.entities);
+        let player_start = <(&Point, &Player)>::query()
+            .iter(ecs)
+            .next()
+            .map_or_else(Point::zero, |(pos, _)| *pos);
+
+        let mut dungeon = Dungeon::default();
+        for saved in self.levels {
+            let mut level = StoredLevel {
+                map: saved.map,
+                theme: saved.theme,
+                ecs: World::default(),
+            };
+            restore_entities(&mut level.ecs, saved.entities);
+            dungeon.store(saved.depth, level);
+        }
+
+        resources.insert(Camera::new(player_start, &self.map));
+        resources.insert(self.map);
+        resources.insert(dungeon);
+        resources.insert(PathfindingCache::new(PATHFINDING_CACHE_SIZE));
+        resources.insert(self.turn_state);
+        resources.insert(self.theme.theme());
+        resources.insert(self.game_log);
+        resources.insert(Targeting::Inactive);
+        resources.insert(InventoryScreen::Closed);
+        resources.insert(self.rng);
+    }
+
+    pub fn write(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let file = File::create(SAVE_FILE)?;
+        ron::ser::to_writer_pretty(file, self, PrettyConfig::new().compact_arrays(true))?;
+        Ok(())
+    }
+
+    pub fn read() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_ron(&std::fs::read_to_string(SAVE_FILE)?)
+    }
+
+    fn from_ron(contents: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let SaveVersion { version } = ron::de::from_str(contents)?;
+        if version != SAVE_VERSION {
+            return Err(format!(
+                "Save file version {} is not supported, expected {}",
+                version, SAVE_VERSION
+            )
+            .into());
+        }
+        Ok(ron::de::from_str(contents)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game() -> (World, Resources) {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        spawn_player(&mut ecs, Point::new(2, 3));
+        let player = *<Entity>::query()
+            .filter(query::component::<Player>())
+            .iter(&ecs)
+            .next()
+            .unwrap();
+        ecs.push((
+            Item,
+            Name {
+                name: "Rusty Sword".to_string(),
+            },
+            Damage { damage: 1 },
+            Equippable {
+                slot: EquipmentSlot::MainHand,
+            },
+            Equipped { by: player },
+        ));
+        ecs.push((
+            Item,
+            Name {
+                name: "Healing Potion".to_string(),
+            },
+            ProvidesHealing { amount: 6 },
+            Carried { by: player },
+        ));
+        ecs.push((
+            Enemy,
+            Point::new(5, 5),
+            Health { current: 1, max: 2 },
+            Hidden,
+        ));
+
+        let mut map = Map::new(12, 8);
+        map.tiles[3] = TileType::Floor;
+        map.tiles[4] = TileType::DoorClosed;
+        map.revealed_tiles[3] = Revealed::Seen;
+        map.revealed_tiles[5] = Revealed::FromMap;
+        resources.insert(map);
+        resources.insert(RandomNumberGenerator::seeded(7));
+        resources.insert(TurnState::AwaitingInput);
+        resources.insert(ThemeKind::Forest.theme());
+        resources.insert(GameLog::default());
+        resources.insert(Dungeon::default());
+        (ecs, resources)
+    }
+
+    fn round_trip(ecs: &World, resources: &Resources) -> (World, Resources) {
+        let contents = ron::ser::to_string(&SaveGame::capture(ecs, resources, 42)).unwrap();
+        let save = SaveGame::from_ron(&contents).unwrap();
+        assert_eq!(save.seed, 42);
+        let mut restored = World::default();
+        let mut restored_resources = Resources::default();
+        save.restore(&mut restored, &mut restored_resources);
+        (restored, restored_resources)
+    }
+
+    #[test]
+    fn round_trip_keeps_components_and_map() {
+        let (ecs, resources) = game();
+        let (restored, restored_resources) = round_trip(&ecs, &resources);
+
+        let (player, pos, health) = <(Entity, &Point, &Health)>::query()
+            .filter(query::component::<Player>())
+            .iter(&restored)
+            .next()
+            .unwrap();
+        assert_eq!(*pos, Point::new(2, 3));
+        assert_eq!(
+            *health,
+            Health {
+                current: 10,
+                max: 10
+            }
+        );
+        let equipped: Vec<&Name> = <(&Name, &Equipped)>::query()
+            .iter(&restored)
+            .filter(|(_, equipped)| equipped.by == *player)
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(equipped.len(), 1);
+        assert_eq!(equipped[0].name, "Rusty Sword");
+        let carried = <(&ProvidesHealing, &Carried)>::query()
+            .iter(&restored)
+            .next()
+            .unwrap();
+        assert_eq!(carried.0.amount, 6);
+        assert_eq!(carried.1.by, *player);
+        assert_eq!(
+            <&Health>::query()
+                .filter(query::component::<Enemy>() & query::component::<Hidden>())
+                .iter(&restored)
+                .next(),
+            Some(&Health { current: 1, max: 2 })
+        );
+
+        let map = resources.get::<Map>().unwrap();
+        let restored_map = restored_resources.get::<Map>().unwrap();
+        assert!(map.tiles == restored_map.tiles);
+        assert_eq!(map.revealed_tiles, restored_map.revealed_tiles);
+
+        // Every component survives, including entity references, if saving again gives the same
+        // file.
+        let first = ron::ser::to_string(&SaveGame::capture(&ecs, &resources, 42)).unwrap();
+        let second =
+            ron::ser::to_string(&SaveGame::capture(&restored, &restored_resources, 42)).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let (ecs, resources) = game();
+        let contents = ron::ser::to_string(&SaveGame::capture(&ecs, &resources, 42)).unwrap();
+        let old = contents.replacen(
+            &format!("version:{}", SAVE_VERSION),
+            &format!("version:{}", SAVE_VERSION - 1),
+            1,
+        );
+        assert_ne!(old, contents);
+        let err = SaveGame::from_ron(&old).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Save file version {} is not supported, expected {}",
+                SAVE_VERSION - 1,
+                SAVE_VERSION
+            )
+        );
+    }
+
+    /// The position of the entity, which tells apart the entities of `one_of_each`.
+    fn position(ecs: &World, entity: Entity) -> Point {
+        *ecs.entry_ref(entity)
+            .unwrap()
+            .get_component::<Point>()
+            .unwrap()
+    }
+
+    /// Checks that the one entity with a `T` has an equal one after loading, comparing entity
+    /// references by the position of the entity they point to.
+    fn same<T: legion::storage::Component, U: PartialEq + std::fmt::Debug>(
+        ecs: &World,
+        restored: &World,
+        view: impl Fn(&World, &T) -> U,
+    ) {
+        let (pos, expected) = <(&Point, &T)>::query()
+            .iter(ecs)
+            .map(|(pos, component)| (*pos, view(ecs, component)))
+            .next()
+            .unwrap();
+        let found = <(&Point, &T)>::query()
+            .iter(restored)
+            .find(|(restored_pos, _)| **restored_pos == pos)
+            .map(|(_, component)| view(restored, component));
+        assert_eq!(
+            found,
+            Some(expected),
+            "{} did not survive",
+            std::any::type_name::<T>()
+        );
+    }
+
+    #[test]
+    fn every_component_survives() {
+        let mut ecs = World::default();
+        let target = ecs.push((Point::new(0, 0),));
+        let mut x = 0;
+        let mut at = || {
+            x += 1;
+            Point::new(x, 0)
+        };
+        ecs.push((
+            at(),
+            ActivateItem {
+                used_by: target,
+                item: target,
+            },
+        ));
+        ecs.push((at(), AmuletOfYala));
+        ecs.push((at(), Armor { armor: 2 }));
+        ecs.push((at(), Carried { by: target }));
+        ecs.push((at(), Damage { damage: 3 }));
+        ecs.push((at(), Enemy));
+        ecs.push((
+            at(),
+            Equippable {
+                slot: EquipmentSlot::OffHand,
+            },
+        ));
+        ecs.push((at(), Equipped { by: target }));
+        ecs.push((at(), FieldOfView::new(5)));
+        ecs.push((at(), Health { current: 4, max: 9 }));
+        ecs.push((at(), Hidden));
+        ecs.push((at(), Item));
+        ecs.push((at(), Player { map_level: 2 }));
+        ecs.push((at(), MovingRandomly));
+        ecs.push((
+            at(),
+            Name {
+                name: "Goblin".to_string(),
+            },
+        ));
+        ecs.push((at(), OpensDoors));
+        ecs.push((at(), ProvidesDungeonMap));
+        ecs.push((at(), ProvidesThrowing));
+        ecs.push((at(), ProvidesHealing { amount: 6 }));
+        ecs.push((at(), ProvidesDepletion));
+        ecs.push((at(), ProvidesDestructionOnLevelProgress));
+        ecs.push((at(), ProvidesEquipment));
+        ecs.push((at(), Ranged { range: 7 }));
+        ecs.push((at(), Regeneration { turns: 3 }));
+        ecs.push((
+            at(),
+            Render {
+                color: ColorPair::new(RED, BLACK),
+                glyph: to_cp437('g'),
+            },
+        ));
+        ecs.push((at(), Resting));
+        ecs.push((
+            at(),
+            RoamingAndChasingPlayer {
+                home_location: Point::new(3, 4),
+                going_to: Some(Point::new(5, 6)),
+            },
+        ));
+        ecs.push((
+            at(),
+            Trap {
+                effect: TrapEffect::Alarm(4),
+            },
+        ));
+        ecs.push((
+            at(),
+            WantsToAttack {
+                attacker: target,
+                victim: target,
+                weapon: Some(target),
+            },
+        ));
+        ecs.push((
+            at(),
+            WantsToShoot {
+                shooter: target,
+                target: Point::new(8, 9),
+                weapon: None,
+            },
+        ));
+        ecs.push((
+            at(),
+            WantsToMove {
+                entity: target,
+                destination: Point::new(1, 2),
+            },
+        ));
+        ecs.push((at(), WantsToSearch { searcher: target }));
+        ecs.push((
+            at(),
+            WantsToCloseDoor {
+                closer: target,
+                door: Point::new(2, 2),
+            },
+        ));
+
+        let (_, resources) = game();
+        let (restored, _) = round_trip(&ecs, &resources);
+        assert_eq!(<&Point>::query().iter(&restored).count(), x as usize + 1);
+
+        same(&ecs, &restored, |ecs, c: &ActivateItem| {
+            (position(ecs, c.used_by), position(ecs, c.item))
+        });
+        same(&ecs, &restored, |_, c: &AmuletOfYala| *c);
+        same(&ecs, &restored, |_, c: &Armor| *c);
+        same(&ecs, &restored, |ecs, c: &Carried| position(ecs, c.by));
+        same(&ecs, &restored, |_, c: &Damage| *c);
+        same(&ecs, &restored, |_, c: &Enemy| *c);
+        same(&ecs, &restored, |_, c: &Equippable| *c);
+        same(&ecs, &restored, |ecs, c: &Equipped| position(ecs, c.by));
+        same(&ecs, &restored, |_, c: &FieldOfView| c.clone());
+        same(&ecs, &restored, |_, c: &Health| *c);
+        same(&ecs, &restored, |_, c: &Hidden| *c);
+        same(&ecs, &restored, |_, c: &Item| *c);
+        same(&ecs, &restored, |_, c: &Player| *c);
+        same(&ecs, &restored, |_, c: &MovingRandomly| *c);
+        same(&ecs, &restored, |_, c: &Name| c.name.clone());
+        same(&ecs, &restored, |_, c: &OpensDoors| *c);
+        same(&ecs, &restored, |_, c: &ProvidesDungeonMap| *c);
+        same(&ecs, &restored, |_, c: &ProvidesThrowing| *c);
+        same(&ecs, &restored, |_, c: &ProvidesHealing| *c);
+        same(&ecs, &restored, |_, c: &ProvidesDepletion| *c);
+        same(
+            &ecs,
+            &restored,
+            |_, c: &ProvidesDestructionOnLevelProgress| *c,
+        );
+        same(&ecs, &restored, |_, c: &ProvidesEquipment| *c);
+        same(&ecs, &restored, |_, c: &Ranged| *c);
+        same(&ecs, &restored, |_, c: &Regeneration| *c);
+        same(&ecs, &restored, |_, c: &Render| *c);
+        same(&ecs, &restored, |_, c: &Resting| *c);
+        same(&ecs, &restored, |_, c: &RoamingAndChasingPlayer| *c);
+        same(&ecs, &restored, |_, c: &Trap| *c);
+        same(&ecs, &restored, |ecs, c: &WantsToAttack| {
+            (
+                position(ecs, c.attacker),
+                position(ecs, c.victim),
+                c.weapon.map(|weapon| position(ecs, weapon)),
+            )
+        });
+        same(&ecs, &restored, |ecs, c: &WantsToShoot| {
+            (
+                position(ecs, c.shooter),
+                c.target,
+                c.weapon.map(|weapon| position(ecs, weapon)),
+            )
+        });
+        same(&ecs, &restored, |ecs, c: &WantsToMove| {
+            (position(ecs, c.entity), c.destination)
+        });
+        same(&ecs, &restored, |ecs, c: &WantsToSearch| {
+            position(ecs, c.searcher)
+        });
+        same(&ecs, &restored, |ecs, c: &WantsToCloseDoor| {
+            (position(ecs, c.closer), c.door)
+        });
+    }
+}
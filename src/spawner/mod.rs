@@ -1,16 +1,33 @@
 mod template;
 
 use crate::prelude::*;
+use std::collections::HashSet;
+pub use template::{EntityType, Templates};
 
 pub fn spawn_level(
     ecs: &mut World,
     resources: &mut Resources,
+    templates: &Templates,
     rng: &mut RandomNumberGenerator,
     level: usize,
     spawn_points: &[Point],
+    vault_spawns: &[(Point, String)],
 ) {
-    let templates = template::Templates::load();
-    templates.spawn_entities(ecs, resources, rng, level, spawn_points);
+    let mut spawn_once_already_spawned = HashSet::new();
+    templates.spawn_named(
+        ecs,
+        resources,
+        vault_spawns,
+        &mut spawn_once_already_spawned,
+    );
+    templates.spawn_entities(
+        ecs,
+        resources,
+        rng,
+        level,
+        spawn_points,
+        &mut spawn_once_already_spawned,
+    );
 }
 
 pub fn spawn_player(ecs: &mut World, pos: Point) {
@@ -27,6 +44,7 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
         },
         FieldOfView::new(8),
         Damage { damage: 1 },
+        Regeneration { turns: 0 },
     ));
 }
 
@@ -44,3 +62,38 @@ pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
         },
     ));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vault_spawns_count_towards_once_templates() {
+        let templates = Templates::load().unwrap();
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let spawn_points: Vec<Point> = (0..NUM_MONSTERS as i32).map(|x| Point::new(x, 1)).collect();
+        let vault_spawns = vec![
+            (Point::new(0, 0), "Rusty Sword".to_string()),
+            (Point::new(1, 0), "Rusty Sword".to_string()),
+        ];
+
+        spawn_level(
+            &mut ecs,
+            &mut resources,
+            &templates,
+            &mut rng,
+            0,
+            &spawn_points,
+            &vault_spawns,
+        );
+
+        let swords: Vec<Point> = <(&Name, &Point)>::query()
+            .iter(&ecs)
+            .filter(|(name, _)| name.name == "Rusty Sword")
+            .map(|(_, pos)| *pos)
+            .collect();
+        assert_eq!(swords, vec![Point::new(0, 0)]);
+    }
+}
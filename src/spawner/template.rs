@@ -2,9 +2,10 @@ use crate::prelude::*;
 use serde::Deserialize;
 
 use legion::systems::CommandBuffer;
-use ron::de::from_reader;
+use ron::de::from_str;
 use std::collections::HashSet;
-use std::fs::File;
+
+const TEMPLATE_FILE: &str = "resources/template.ron";
 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Template {
@@ -13,15 +14,31 @@ pub struct Template {
     pub frequency: Frequency,
     pub name: String,
     pub glyph: char,
-    pub provides: Option<Vec<(String, i32)>>,
+    pub provides: Option<Vec<Provides>>,
     pub hp: Option<i32>,
     pub base_damage: Option<i32>,
+    pub armor: Option<i32>,
+    pub slot: Option<EquipmentSlot>,
+    pub range: Option<i32>,
+    pub opens_doors: Option<bool>,
+    pub trap: Option<TrapEffect>,
 }
 
 #[derive(Clone, Deserialize, Debug, PartialEq)]
 pub enum EntityType {
     Enemy,
     Item,
+    Trap,
+}
+
+#[derive(Clone, Deserialize, Debug, PartialEq)]
+pub enum Provides {
+    Healing(i32),
+    Depletion,
+    DestructionOnLevelProgress,
+    Equipment,
+    MagicMap,
+    Throwing,
 }
 
 #[derive(Clone, Deserialize, Debug, PartialEq)]
@@ -36,9 +53,225 @@ pub struct Templates {
 }
 
 impl Templates {
-    pub fn load() -> Self {
-        let file = File::open("resources/template.ron").expect("Failed opening file");
-        from_reader(file).expect("Unable to load templates")
+    pub fn load() -> Result<Self, String> {
+        let source = std::fs::read_to_string(TEMPLATE_FILE)
+            .map_err(|err| format!("Unable to read {}: {}", TEMPLATE_FILE, err))?;
+        from_str(&source).map_err(|err| {
+            match Self::template_name_before(&source, err.position.line) {
+                Some(name) => format!(
+                    "{}:{}: in template [{}]: {}",
+                    TEMPLATE_FILE, err.position, name, err.code
+                ),
+                None => format!("{}:{}: {}", TEMPLATE_FILE, err.position, err.code),
+            }
+        })
+    }
+
+    fn template_name_before(source: &str, line: usize) -> Option<String> {
+        let lines: Vec<&str> = source.lines().collect();
+        let start = lines
+            .iter()
+            .take(line)
+            .rposition(|l| l.contains("Template("))?;
+        lines[start..]
+            .iter()
+            .enumerate()
+            .take_while(|(offset, l)| *offset == 0 || !l.contains("Template("))
+            .find_map(|(_, l)| {
+                let rest = &l[l.find("name")? + "name".len()..];
+                let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+                rest.strip_prefix('"')?
+                    .split('"')
+                    .next()
+                    .map(str::to_string)
+            })
+    }
+
+    /// Lists every problem that would otherwise only show up while playing, each prefixed with
+    /// the name of the template it concerns.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        let mut names = HashSet::new();
+        for template in self.entities.iter() {
+            let name = &template.name;
+            if !names.insert(name) {
+                problems.push(format!("[{}] Duplicate template name", name));
+            }
+            if template.levels.is_empty() {
+                problems.push(format!("[{}] No levels to spawn on", name));
+            }
+            let mut unused_levels: Vec<&usize> = template
+                .levels
+                .iter()
+                .filter(|level| **level >= NUM_LEVELS)
+                .collect();
+            unused_levels.sort();
+            if !unused_levels.is_empty() {
+                problems.push(format!(
+                    "[{}] Levels {:?} do not exist, the dungeon has {} levels",
+                    name, unused_levels, NUM_LEVELS
+                ));
+            }
+            if template.frequency == Frequency::Repeated(0) {
+                problems.push(format!("[{}] Repeated(0) never spawns", name));
+            }
+            if let Some(damage) = template.base_damage {
+                if damage < 1 {
+                    problems.push(format!(
+                        "[{}] base_damage {} must be positive",
+                        name, damage
+                    ));
+                }
+            }
+            if let Some(armor) = template.armor {
+                if armor < 1 {
+                    problems.push(format!("[{}] armor {} must be positive", name, armor));
+                }
+            }
+            if let Some(range) = template.range {
+                if range < 2 {
+                    problems.push(format!("[{}] range {} must be at least 2", name, range));
+                }
+            }
+            let provides = template.provides.as_deref().unwrap_or_default();
+            if template.trap.is_some() && template.entity_type != EntityType::Trap {
+                problems.push(format!("[{}] Only traps have a trap effect", name));
+            }
+            match template.entity_type {
+                EntityType::Enemy => {
+                    match template.hp {
+                        None => problems.push(format!("[{}] Enemy has no hp", name)),
+                        Some(hp) if hp < 1 => {
+                            problems.push(format!("[{}] hp {} must be positive", name, hp))
+                        }
+                        _ => {}
+                    }
+                    if template.base_damage.is_none() {
+                        problems.push(format!("[{}] Enemy has no base_damage", name));
+                    }
+                    if !provides.is_empty() {
+                        problems.push(format!("[{}] Enemies cannot provide effects", name));
+                    }
+                    if template.slot.is_some() {
+                        problems.push(format!("[{}] Enemies have no equipment slot", name));
+                    }
+                }
+                EntityType::Item => {
+                    if template.hp.is_some() {
+                        problems.push(format!("[{}] Items have no hp", name));
+                    }
+                    if template.opens_doors.is_some() {
+                        problems.push(format!("[{}] Items cannot open doors", name));
+                    }
+                    let equipment = provides.contains(&Provides::Equipment);
+                    let throwing = provides.contains(&Provides::Throwing);
+                    if template.base_damage.is_some() && !equipment && !throwing {
+                        problems.push(format!(
+                            "[{}] base_damage on an item that can neither be equipped nor thrown",
+                            name
+                        ));
+                    }
+                    if template.armor.is_some() && !equipment {
+                        problems.push(format!(
+                            "[{}] armor on an item that cannot be equipped",
+                            name
+                        ));
+                    }
+                    if template.range.is_some() && !equipment && !throwing {
+                        problems.push(format!(
+                            "[{}] range on an item that can neither be equipped nor thrown",
+                            name
+                        ));
+                    }
+                    if throwing {
+                        if equipment {
+                            problems.push(format!(
+                                "[{}] Items cannot be both thrown and equipped",
+                                name
+                            ));
+                        }
+                        if template.range.is_none() {
+                            problems.push(format!("[{}] Throwing item has no range", name));
+                        }
+                        if template.base_damage.is_none() {
+                            problems.push(format!("[{}] Throwing item has no base_damage", name));
+                        }
+                    }
+                    match (equipment, template.slot) {
+                        (true, None) => problems.push(format!("[{}] Equipment has no slot", name)),
+                        (false, Some(_)) => problems.push(format!(
+                            "[{}] slot on an item that cannot be equipped",
+                            name
+                        )),
+                        _ => {}
+                    }
+                    if provides.is_empty() {
+                        problems.push(format!("[{}] Item provides nothing", name));
+                    }
+                }
+                EntityType::Trap => {
+                    match template.trap {
+                        None => problems.push(format!("[{}] Trap has no trap effect", name)),
+                        Some(TrapEffect::Damage(damage)) if damage < 1 => {
+                            problems.push(format!("[{}] Damage({}) must be positive", name, damage))
+                        }
+                        Some(TrapEffect::Alarm(radius)) if radius < 1 => problems.push(format!(
+                            "[{}] Alarm({}) must reach at least 1 tile",
+                            name, radius
+                        )),
+                        _ => {}
+                    }
+                    if template.hp.is_some()
+                        || template.base_damage.is_some()
+                        || template.armor.is_some()
+                        || template.range.is_some()
+                        || template.slot.is_some()
+                        || template.opens_doors.is_some()
+                        || !provides.is_empty()
+                    {
+                        problems.push(format!(
+                            "[{}] Traps only have a trap effect, not stats or effects",
+                            name
+                        ));
+                    }
+                }
+            }
+            provides.iter().for_each(|provides| {
+                if let Provides::Healing(amount) = provides {
+                    if *amount < 1 {
+                        problems.push(format!("[{}] Healing({}) must be positive", name, amount));
+                    }
+                }
+            });
+        }
+
+        for level in 0..NUM_LEVELS {
+            let spawnable: Vec<&Template> = self
+                .entities
+                .iter()
+                .filter(|template| template.levels.contains(&level))
+                .filter(|template| template.frequency != Frequency::Repeated(0))
+                .collect();
+            if spawnable.is_empty() {
+                problems.push(format!("[level {}] No template can spawn", level));
+            }
+            let once: Vec<&&Template> = spawnable
+                .iter()
+                .filter(|template| template.frequency == Frequency::Once)
+                .collect();
+            once.iter().skip(NUM_MONSTERS).for_each(|template| {
+                problems.push(format!(
+                    "[{}] No spawn point left on level {}, which has {} for {} Once templates",
+                    template.name,
+                    level,
+                    NUM_MONSTERS,
+                    once.len()
+                ))
+            });
+        }
+
+        problems
     }
 
     pub fn spawn_entities(
@@ -48,6 +281,7 @@ impl Templates {
         rng: &mut RandomNumberGenerator,
         level: usize,
         spawn_points: &[Point],
+        spawn_once_already_spawned: &mut HashSet<String>,
     ) {
         let mut available_entities = Vec::new();
         self.entities
@@ -65,8 +299,14 @@ impl Templates {
             });
 
         let mut commands = CommandBuffer::new(ecs);
-        let mut spawn_once_already_spawned = HashSet::new();
         spawn_points.iter().for_each(|point| {
+            let exhausted = available_entities.iter().all(|entity| {
+                entity.frequency == Frequency::Once
+                    && spawn_once_already_spawned.contains(&entity.name)
+            });
+            if exhausted {
+                return;
+            }
             let entity = loop {
                 let entity = *rng.random_slice_entry(&available_entities).unwrap();
                 match entity.frequency {
@@ -87,6 +327,29 @@ impl Templates {
         commands.flush(ecs, resources);
     }
 
+    /// Spawns the template with each given name at its point, for entities a level always has.
+    /// A `Once` template already in `spawn_once_already_spawned` is left out.
+    pub fn spawn_named(
+        &self,
+        ecs: &mut World,
+        resources: &mut Resources,
+        spawns: &[(Point, String)],
+        spawn_once_already_spawned: &mut HashSet<String>,
+    ) {
+        let mut commands = CommandBuffer::new(ecs);
+        spawns.iter().for_each(|(point, name)| {
+            if let Some(template) = self.entities.iter().find(|t| t.name == *name) {
+                if template.frequency == Frequency::Once
+                    && !spawn_once_already_spawned.insert(template.name.clone())
+                {
+                    return;
+                }
+                self.spawn_entity(*point, template, &mut commands);
+            }
+        });
+        commands.flush(ecs, resources);
+    }
+
     pub fn spawn_entity(&self, point: Point, template: &Template, commands: &mut CommandBuffer) {
         let entity = commands.push((
             point,
@@ -101,35 +364,54 @@ impl Templates {
 
         if let Some(damage) = &template.base_damage {
             commands.add_component(entity, Damage { damage: *damage });
-            if template.entity_type == EntityType::Item {
-                commands.add_component(entity, Weapon {});
-            }
+        }
+
+        if let Some(armor) = &template.armor {
+            commands.add_component(entity, Armor { armor: *armor });
+        }
+
+        if let Some(range) = &template.range {
+            commands.add_component(entity, Ranged { range: *range });
         }
 
         match template.entity_type {
             EntityType::Item => {
                 commands.add_component(entity, Item {});
                 if let Some(effects) = &template.provides {
-                    effects
-                        .iter()
-                        .for_each(|(provides, n)| match provides.as_str() {
-                            "Healing" => {
-                                commands.add_component(entity, ProvidesHealing { amount: *n })
-                            }
-                            "Depletion" => commands.add_component(entity, ProvidesDepletion {}),
-                            "DestructionOnLevelProgress" => commands
-                                .add_component(entity, ProvidesDestructionOnLevelProgress {}),
-                            "Equipment" => commands.add_component(entity, ProvidesEquipment {}),
-                            "MagicMap" => commands.add_component(entity, ProvidesDungeonMap {}),
-                            _ => {
-                                println!("Warning: we don't knpow how to provide {}", provides)
-                            }
-                        });
+                    effects.iter().for_each(|provides| match provides {
+                        Provides::Healing(amount) => {
+                            commands.add_component(entity, ProvidesHealing { amount: *amount })
+                        }
+                        Provides::Depletion => commands.add_component(entity, ProvidesDepletion {}),
+                        Provides::DestructionOnLevelProgress => {
+                            commands.add_component(entity, ProvidesDestructionOnLevelProgress {})
+                        }
+                        Provides::Equipment => {
+                            commands.add_component(entity, ProvidesEquipment {});
+                            commands.add_component(
+                                entity,
+                                Equippable {
+                                    slot: template.slot.unwrap_or(EquipmentSlot::MainHand),
+                                },
+                            );
+                        }
+                        Provides::MagicMap => commands.add_component(entity, ProvidesDungeonMap {}),
+                        Provides::Throwing => commands.add_component(entity, ProvidesThrowing {}),
+                    });
+                }
+            }
+            EntityType::Trap => {
+                if let Some(effect) = template.trap {
+                    commands.add_component(entity, Trap { effect });
                 }
+                commands.add_component(entity, Hidden);
             }
             EntityType::Enemy => {
                 commands.add_component(entity, Enemy {});
                 commands.add_component(entity, FieldOfView::new(6));
+                if template.opens_doors == Some(true) {
+                    commands.add_component(entity, OpensDoors);
+                }
                 commands.add_component(
                     entity,
                     RoamingAndChasingPlayer {
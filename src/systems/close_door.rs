@@ -0,0 +1,119 @@
+use super::movement::set_door;
+use crate::prelude::*;
+
+/// Closes each door asked for, unless something has stepped into it since.
+#[system]
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(WantsToCloseDoor)]
+pub fn close_door(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] pathfinding: &mut PathfindingCache,
+    #[resource] log: &mut GameLog,
+) {
+    let closes: Vec<(Entity, Entity, Point)> = <(Entity, &WantsToCloseDoor)>::query()
+        .iter(ecs)
+        .map(|(message, close)| (*message, close.closer, close.door))
+        .collect();
+
+    for (message, closer, door) in closes {
+        commands.remove(message);
+        let open = map.try_idx(door).map(|idx| map.tiles[idx]) == Some(TileType::DoorOpen);
+        if !open || <&Point>::query().iter(ecs).any(|pos| *pos == door) {
+            continue;
+        }
+        set_door(
+            door,
+            TileType::DoorClosed,
+            &[],
+            map,
+            pathfinding,
+            ecs,
+            commands,
+        );
+        let is_player = ecs
+            .entry_ref(closer)
+            .is_ok_and(|entry| entry.get_component::<Player>().is_ok());
+        if is_player {
+            log.add("You close the door.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(ecs: &mut World, resources: &mut Resources) {
+        Schedule::builder()
+            .add_system(close_door_system())
+            .build()
+            .execute(ecs, resources);
+    }
+
+    fn resources(map: Map) -> Resources {
+        let mut resources = Resources::default();
+        let mut pathfinding = PathfindingCache::new(PATHFINDING_CACHE_SIZE);
+        pathfinding.dijkstra_map(&map, 0, false);
+        resources.insert(map);
+        resources.insert(pathfinding);
+        resources.insert(GameLog::default());
+        resources
+    }
+
+    #[test]
+    fn closing_a_door_blocks_sight_and_paths() {
+        let door = Point::new(2, 1);
+        let mut map = Map::new(5, 3);
+        let idx = map.map_idx(door);
+        map.tiles[idx] = TileType::DoorOpen;
+        let mut resources = resources(map);
+        let mut ecs = World::default();
+        let mut fov = FieldOfView::new(8);
+        fov.is_dirty = false;
+        let player = ecs.push((Player { map_level: 0 }, Point::new(1, 1), fov));
+        ecs.push((
+            (),
+            WantsToCloseDoor {
+                closer: player,
+                door,
+            },
+        ));
+        run(&mut ecs, &mut resources);
+
+        assert!(resources.get::<Map>().unwrap().tiles[idx] == TileType::DoorClosed);
+        assert_eq!(
+            resources.get::<PathfindingCache>().unwrap().memory_usage(),
+            0
+        );
+        let entry = ecs.entry_ref(player).unwrap();
+        assert!(entry.get_component::<FieldOfView>().unwrap().is_dirty);
+        assert_eq!(<&WantsToCloseDoor>::query().iter(&ecs).count(), 0);
+    }
+
+    #[test]
+    fn an_occupied_door_stays_open() {
+        let door = Point::new(2, 1);
+        let mut map = Map::new(5, 3);
+        let idx = map.map_idx(door);
+        map.tiles[idx] = TileType::DoorOpen;
+        let mut resources = resources(map);
+        let mut ecs = World::default();
+        let player = ecs.push((Player { map_level: 0 }, Point::new(1, 1)));
+        ecs.push((Enemy, door));
+        ecs.push((
+            (),
+            WantsToCloseDoor {
+                closer: player,
+                door,
+            },
+        ));
+        run(&mut ecs, &mut resources);
+
+        assert!(resources.get::<Map>().unwrap().tiles[idx] == TileType::DoorOpen);
+        assert!(resources.get::<PathfindingCache>().unwrap().memory_usage() > 0);
+    }
+}
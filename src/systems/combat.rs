@@ -1,18 +1,39 @@
 use crate::prelude::*;
 
+fn name_of(ecs: &SubWorld, entity: Entity) -> String {
+    ecs.entry_ref(entity)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Name>()
+                .ok()
+                .map(|name| name.name.clone())
+        })
+        .unwrap_or_else(|| "Something".to_string())
+}
+
+/// The damage left after armor, which never heals.
+fn mitigated(damage: i32, armor: i32) -> i32 {
+    i32::max(0, damage - armor)
+}
+
 #[system]
 #[read_component(WantsToAttack)]
 #[read_component(Player)]
 #[write_component(Health)]
 #[read_component(Damage)]
 #[read_component(Equipped)]
-pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
-    let victims: Vec<(Entity, Entity, Entity)> = <(Entity, &WantsToAttack)>::query()
-        .iter(ecs)
-        .map(|(entity, attack)| (*entity, attack.attacker, attack.victim))
-        .collect();
+#[read_component(Armor)]
+#[read_component(Name)]
+#[read_component(Ranged)]
+pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] log: &mut GameLog) {
+    let victims: Vec<(Entity, Entity, Entity, Option<Entity>)> =
+        <(Entity, &WantsToAttack)>::query()
+            .iter(ecs)
+            .map(|(entity, attack)| (*entity, attack.attacker, attack.victim, attack.weapon))
+            .collect();
 
-    victims.iter().for_each(|(message, attacker, victim)| {
+    for (message, attacker, victim, weapon) in victims.iter() {
         let base_damage = if let Ok(v) = ecs.entry_ref(*attacker) {
             if let Ok(dmg) = v.get_component::<Damage>() {
                 dmg.damage
@@ -23,29 +44,217 @@ pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
             0
         };
 
-        let weapon_damage: i32 = <(&Equipped, &Damage)>::query()
+        let weapon_damage: i32 = if let Some(weapon) = weapon {
+            ecs.entry_ref(*weapon)
+                .ok()
+                .and_then(|v| v.get_component::<Damage>().ok().map(|dmg| dmg.damage))
+                .unwrap_or(0)
+        } else {
+            <(&Equipped, &Damage)>::query()
+                .filter(!component::<Ranged>())
+                .iter(ecs)
+                .filter(|(equipped, _)| equipped.by == *attacker)
+                .map(|(_, dmg)| dmg.damage)
+                .sum()
+        };
+
+        let base_armor = if let Ok(v) = ecs.entry_ref(*victim) {
+            if let Ok(armor) = v.get_component::<Armor>() {
+                armor.armor
+            } else {
+                0
+            }
+        } else {
+            0
+        };
+
+        let equipped_armor: i32 = <(&Equipped, &Armor)>::query()
             .iter(ecs)
-            .filter(|(equipped, _)| equipped.by == *attacker)
-            .map(|(_, dmg)| dmg.damage)
+            .filter(|(equipped, _)| equipped.by == *victim)
+            .map(|(_, armor)| armor.armor)
             .sum();
 
-        let final_damage = base_damage + weapon_damage;
+        let final_damage = mitigated(base_damage + weapon_damage, base_armor + equipped_armor);
 
         let is_player = ecs
             .entry_ref(*victim)
             .unwrap()
             .get_component::<Player>()
             .is_ok();
+        let attacker_name = name_of(ecs, *attacker);
+        let victim_name = name_of(ecs, *victim);
         if let Ok(health) = ecs
             .entry_mut(*victim)
             .unwrap()
             .get_component_mut::<Health>()
         {
             health.current -= final_damage;
-            if health.current < 1 && !is_player {
+            let slain = health.current < 1;
+            if slain && !is_player {
                 commands.remove(*victim);
             }
+            log.add(match (is_player, final_damage, slain) {
+                (true, 0, _) => format!("{}'s blow glances off your armor.", attacker_name),
+                (true, _, _) => format!("{} hits you for {}.", attacker_name, final_damage),
+                (false, 0, _) => format!("Your blow glances off the {}.", victim_name),
+                (false, _, true) => format!("You slay the {}.", victim_name),
+                (false, _, false) => {
+                    format!("You hit the {} for {}.", victim_name, final_damage)
+                }
+            });
         }
         commands.remove(*message);
-    });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::systems::use_items::use_items_system;
+
+    fn run(ecs: &mut World) {
+        let mut resources = Resources::default();
+        resources.insert(Map::new(5, 5));
+        resources.insert(GameLog::default());
+        Schedule::builder()
+            .add_system(use_items_system())
+            .flush()
+            .add_system(combat_system())
+            .build()
+            .execute(ecs, &mut resources);
+    }
+
+    fn named(name: &str) -> Name {
+        Name {
+            name: name.to_string(),
+        }
+    }
+
+    fn gear(ecs: &mut World, owner: Entity, name: &str, slot: EquipmentSlot) -> Entity {
+        ecs.push((
+            Item,
+            named(name),
+            Equippable { slot },
+            ProvidesEquipment,
+            Carried { by: owner },
+        ))
+    }
+
+    fn health(ecs: &World, entity: Entity) -> i32 {
+        ecs.entry_ref(entity)
+            .unwrap()
+            .get_component::<Health>()
+            .unwrap()
+            .current
+    }
+
+    fn attack(ecs: &mut World, attacker: Entity, victim: Entity) {
+        ecs.push((
+            (),
+            WantsToAttack {
+                attacker,
+                victim,
+                weapon: None,
+            },
+        ));
+    }
+
+    #[test]
+    fn equipped_armor_adds_up_and_mitigates_both_ways() {
+        let mut ecs = World::default();
+        let player = ecs.push((
+            Player { map_level: 0 },
+            named("Player"),
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Damage { damage: 1 },
+        ));
+        let sword = gear(&mut ecs, player, "Sword", EquipmentSlot::MainHand);
+        ecs.entry(sword)
+            .unwrap()
+            .add_component(Damage { damage: 2 });
+        let shield = gear(&mut ecs, player, "Shield", EquipmentSlot::OffHand);
+        ecs.entry(shield).unwrap().add_component(Armor { armor: 1 });
+        let mail = gear(&mut ecs, player, "Mail", EquipmentSlot::Body);
+        ecs.entry(mail).unwrap().add_component(Armor { armor: 2 });
+        let ogre = ecs.push((
+            Enemy,
+            named("Ogre"),
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Damage { damage: 5 },
+            Armor { armor: 1 },
+        ));
+        let goblin = ecs.push((
+            Enemy,
+            named("Goblin"),
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Damage { damage: 2 },
+        ));
+        for item in [sword, shield, mail] {
+            ecs.push((
+                (),
+                ActivateItem {
+                    used_by: player,
+                    item,
+                },
+            ));
+        }
+        run(&mut ecs);
+        for item in [sword, shield, mail] {
+            let entry = ecs.entry_ref(item).unwrap();
+            assert!(entry.get_component::<Equipped>().is_ok());
+        }
+
+        attack(&mut ecs, ogre, player);
+        attack(&mut ecs, goblin, player);
+        attack(&mut ecs, player, ogre);
+        run(&mut ecs);
+        // The ogre's 5 less 3 armor from the shield and mail, and nothing from the goblin.
+        assert_eq!(health(&ecs, player), 8);
+        // The player's 1 plus 2 from the sword, less the ogre's own armor.
+        assert_eq!(health(&ecs, ogre), 8);
+    }
+
+    #[test]
+    fn ranged_weapons_add_nothing_in_melee() {
+        let mut ecs = World::default();
+        let player = ecs.push((
+            Player { map_level: 0 },
+            named("Player"),
+            Health {
+                current: 10,
+                max: 10,
+            },
+            Damage { damage: 1 },
+        ));
+        ecs.push((
+            Item,
+            named("Bow"),
+            Equippable {
+                slot: EquipmentSlot::MainHand,
+            },
+            Ranged { range: 6 },
+            Damage { damage: 3 },
+            Equipped { by: player },
+        ));
+        let goblin = ecs.push((
+            Enemy,
+            named("Goblin"),
+            Health {
+                current: 10,
+                max: 10,
+            },
+        ));
+        attack(&mut ecs, player, goblin);
+        run(&mut ecs);
+        assert_eq!(health(&ecs, goblin), 9);
+    }
 }
@@ -3,13 +3,17 @@ use crate::prelude::*;
 #[system]
 #[read_component(Health)]
 #[read_component(Point)]
-pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resource] map: &Map) {
+pub fn end_turn(
+    ecs: &SubWorld,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] log: &mut GameLog,
+) {
     let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
     let amulet_pos = <&Point>::query()
         .filter(component::<AmuletOfYala>())
         .iter(ecs)
         .next();
-    let current_state = turn_state.clone();
+    let current_state = *turn_state;
 
     let mut new_state = match current_state {
         TurnState::AwaitingInput => return,
@@ -22,13 +26,18 @@ pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resour
         if hp.current < 1 {
             new_state = TurnState::GameOver;
         }
-        if map.tiles[Map::map_idx(*pos)] == TileType::Exit {
-            new_state = TurnState::NextLevel;
-        }
         if Some(pos) == amulet_pos {
             new_state = TurnState::Victory;
         }
     });
 
+    match new_state {
+        TurnState::GameOver => log.add("You have been slain."),
+        TurnState::NextLevel => log.add("You descend the stairs."),
+        TurnState::PreviousLevel => log.add("You climb the stairs."),
+        TurnState::Victory => log.add("You take the Amulet of Yala."),
+        _ => {}
+    }
+
     *turn_state = new_state;
 }
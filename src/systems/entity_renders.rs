@@ -2,8 +2,10 @@ use crate::prelude::*;
 
 #[system]
 #[read_component(FieldOfView)]
+#[read_component(Hidden)]
 #[read_component(Point)]
 #[read_component(Render)]
+#[read_component(Trap)]
 pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
     let (player_fov, player_pos) = <(&FieldOfView, &Point)>::query()
         .filter(component::<Player>())
@@ -15,8 +17,13 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
     draw_batch.target(1);
     let offset = Point::new(camera.left_x, camera.top_y);
 
-    <(&Point, &Render)>::query()
+    let mut traps =
+        <(&Point, &Render)>::query().filter(component::<Trap>() & !component::<Hidden>());
+    let mut others =
+        <(&Point, &Render)>::query().filter(!component::<Trap>() & !component::<Hidden>());
+    traps
         .iter(ecs)
+        .chain(others.iter(ecs))
         .filter(|(pos, _)| player_fov.visible_tiles.contains(pos))
         .for_each(|(pos, render)| {
             let dist = DistanceAlg::Pythagoras.distance2d(*player_pos, *pos);
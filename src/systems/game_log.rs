@@ -0,0 +1,23 @@
+use crate::prelude::*;
+
+const LOG_LINES: i32 = 8;
+
+#[system]
+pub fn game_log(#[resource] log: &GameLog, #[resource] keymap: &Keymap) {
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+
+    let top = SCREEN_HEIGHT * 2 - 3 - LOG_LINES;
+    draw_batch.print_color(
+        Point::new(1, top),
+        keymap.log_title(log.is_scrolled()),
+        ColorPair::new(YELLOW, BLACK),
+    );
+    log.visible(LOG_LINES as usize)
+        .zip(top + 1..)
+        .for_each(|(entry, y)| {
+            draw_batch.print(Point::new(1, y), entry);
+        });
+
+    draw_batch.submit(10050).expect("Batch error");
+}
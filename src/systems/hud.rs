@@ -1,14 +1,16 @@
 use crate::prelude::*;
-use std::collections::BTreeMap;
 use std::iter::Iterator;
 
 #[system]
 #[read_component(Carried)]
 #[read_component(Equipped)]
+#[read_component(Equippable)]
+#[read_component(Armor)]
 #[read_component(Health)]
+#[read_component(Item)]
 #[read_component(Name)]
 #[read_component(Player)]
-pub fn hud(ecs: &SubWorld) {
+pub fn hud(ecs: &SubWorld, #[resource] keymap: &Keymap, #[resource] map: &Map) {
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
 
@@ -18,25 +20,9 @@ pub fn hud(ecs: &SubWorld) {
         .map(|(entity, health, player)| (*entity, health, player.map_level))
         .unwrap();
 
-    let mut carried: BTreeMap<String, usize> = BTreeMap::new();
-    <(&Name, &Carried)>::query()
-        .filter(component::<Item>())
-        .iter(ecs)
-        .filter(|(_, carried)| carried.by == player_entity)
-        .for_each(|(name, _)| {
-            if let Some(entry) = carried.get_mut(&name.name) {
-                *entry += 1;
-            } else {
-                carried.insert(name.name.clone(), 1);
-            }
-        });
-
     let mut y = 3;
-    for (item, count) in carried {
-        draw_batch.print(
-            Point::new(3, y),
-            format!("{} : {} x {}", y - 2, count, item),
-        );
+    for (slot, (_, name, _)) in carried_items(ecs, player_entity).iter().enumerate() {
+        draw_batch.print(Point::new(3, y), format!("{} : {}", slot + 1, name));
         y += 1;
     }
     if y > 3 {
@@ -48,12 +34,19 @@ pub fn hud(ecs: &SubWorld) {
     }
 
     y = 3;
-    <(&Name, &Equipped)>::query()
+    let mut armor = 0;
+    <(Entity, &Name, &Equipped, &Equippable)>::query()
         .filter(component::<Item>())
         .iter(ecs)
-        .filter(|(_, equipped)| equipped.by == player_entity)
-        .for_each(|(name, _)| {
-            draw_batch.print_right(Point::new(SCREEN_WIDTH * 2 - 3, y), &name.name);
+        .filter(|(_, _, equipped, _)| equipped.by == player_entity)
+        .for_each(|(entity, name, _, equippable)| {
+            if let Ok(item_armor) = ecs.entry_ref(*entity).unwrap().get_component::<Armor>() {
+                armor += item_armor.armor;
+            }
+            draw_batch.print_right(
+                Point::new(SCREEN_WIDTH * 2 - 3, y),
+                format!("{} ({})", name.name, equippable.slot.name()),
+            );
             y += 1;
         });
     if y > 3 {
@@ -63,11 +56,17 @@ pub fn hud(ecs: &SubWorld) {
             ColorPair::new(YELLOW, BLACK),
         );
     }
+    if armor > 0 {
+        draw_batch.print_color_right(
+            Point::new(SCREEN_WIDTH * 2 - 3, y),
+            format!("Armor: {}", armor),
+            ColorPair::new(CYAN, BLACK),
+        );
+    }
 
-    draw_batch.print_centered(
-        0,
-        "Explore the Dungeon. Cursor keys to move. G to pick up item and number to use it.",
-    );
+    for (y, line) in keymap.help(map.diagonal_movement).iter().enumerate() {
+        draw_batch.print_centered(y as i32, line);
+    }
     draw_batch.bar_horizontal(
         Point::new(0, SCREEN_HEIGHT * 2 - 1),
         SCREEN_WIDTH * 2,
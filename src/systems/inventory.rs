@@ -0,0 +1,119 @@
+use crate::prelude::*;
+
+const LEFT: i32 = 30;
+const TOP: i32 = 20;
+const WIDTH: i32 = SCREEN_WIDTH * 2 - LEFT * 2;
+const HEIGHT: i32 = SCREEN_HEIGHT * 2 - TOP * 2;
+const LIST_ROWS: usize = (HEIGHT - 6) as usize;
+const DETAILS_X: i32 = LEFT + WIDTH / 2;
+
+#[system]
+#[read_component(Armor)]
+#[read_component(Carried)]
+#[read_component(Damage)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(Item)]
+#[read_component(Name)]
+#[read_component(Player)]
+#[read_component(ProvidesDepletion)]
+#[read_component(ProvidesDestructionOnLevelProgress)]
+#[read_component(ProvidesDungeonMap)]
+#[read_component(ProvidesHealing)]
+#[read_component(ProvidesThrowing)]
+#[read_component(Ranged)]
+pub fn inventory(
+    ecs: &SubWorld,
+    #[resource] inventory: &InventoryScreen,
+    #[resource] keymap: &Keymap,
+) {
+    let InventoryScreen::Open { selected } = *inventory else {
+        return;
+    };
+    let player = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .copied()
+        .unwrap();
+    let items = inventory_items(ecs, player);
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+
+    // The console has no background, so solid blocks hide the map behind the screen.
+    let frame = Rect::with_size(LEFT, TOP, WIDTH - 1, HEIGHT - 1);
+    draw_batch.fill_region(frame, ColorPair::new(BLACK, BLACK), to_cp437('█'));
+    draw_batch.draw_hollow_double_box(frame, ColorPair::new(WHITE, BLACK));
+    draw_batch.print_color_centered_at(
+        Point::new(LEFT + WIDTH / 2, TOP),
+        " Inventory ",
+        ColorPair::new(YELLOW, BLACK),
+    );
+    draw_batch.print_centered_at(
+        Point::new(LEFT + WIDTH / 2, TOP + HEIGHT - 2),
+        keymap.inventory_help(),
+    );
+
+    if items.is_empty() {
+        draw_batch.print(
+            Point::new(LEFT + 2, TOP + 2),
+            "You are not carrying anything.",
+        );
+        draw_batch.submit(10300).expect("Batch error");
+        return;
+    }
+    let selected = selected.min(items.len() - 1);
+
+    // Scroll the list so that the selected item stays in view.
+    let first = (selected + 1).saturating_sub(LIST_ROWS);
+    items
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(LIST_ROWS)
+        .for_each(|(idx, (_, name, equipped))| {
+            let y = TOP + 2 + (idx - first) as i32;
+            let line = if *equipped {
+                format!("   {} (equipped)", name)
+            } else {
+                format!("{}. {}", idx + 1, name)
+            };
+            if idx == selected {
+                draw_batch.print_color(
+                    Point::new(LEFT + 2, y),
+                    format!("> {}", line),
+                    ColorPair::new(YELLOW, BLACK),
+                );
+            } else {
+                draw_batch.print(Point::new(LEFT + 4, y), line);
+            }
+        });
+    if items.len() > LIST_ROWS {
+        draw_batch.print_color(
+            Point::new(LEFT + 4, TOP + 3 + LIST_ROWS as i32),
+            format!(
+                "{}-{} of {}",
+                first + 1,
+                (first + LIST_ROWS).min(items.len()),
+                items.len()
+            ),
+            ColorPair::new(GRAY, BLACK),
+        );
+    }
+
+    let (item, name, _) = &items[selected];
+    draw_batch.print_color(
+        Point::new(DETAILS_X, TOP + 2),
+        name,
+        ColorPair::new(YELLOW, BLACK),
+    );
+    describe_item(ecs, *item)
+        .iter()
+        .enumerate()
+        .for_each(|(row, line)| {
+            draw_batch.print(Point::new(DETAILS_X, TOP + 4 + row as i32), line);
+        });
+
+    draw_batch.submit(10300).expect("Batch error");
+}
@@ -0,0 +1,130 @@
+use crate::prelude::*;
+
+/// Uses, equips or takes off the item and returns whether it took a turn.
+pub(super) fn use_selected(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    player: Entity,
+    (item, name, equipped): &(Entity, String, bool),
+    log: &mut GameLog,
+) -> bool {
+    if *equipped {
+        commands.remove_component::<Equipped>(*item);
+        commands.add_component(*item, Carried { by: player });
+        log.add(format!("You take off the {}.", name));
+        return true;
+    }
+    let entry = ecs.entry_ref(*item).unwrap();
+    let usable = entry.get_component::<ProvidesHealing>().is_ok()
+        || entry.get_component::<ProvidesDungeonMap>().is_ok()
+        || entry.get_component::<ProvidesEquipment>().is_ok();
+    if usable {
+        commands.push((
+            (),
+            ActivateItem {
+                used_by: player,
+                item: *item,
+            },
+        ));
+    } else {
+        log.add(format!("You can't use the {}.", name));
+    }
+    usable
+}
+
+fn drop_selected(
+    commands: &mut CommandBuffer,
+    player_pos: Point,
+    (item, name, equipped): &(Entity, String, bool),
+    log: &mut GameLog,
+) {
+    if *equipped {
+        commands.remove_component::<Equipped>(*item);
+    } else {
+        commands.remove_component::<Carried>(*item);
+    }
+    commands.add_component(*item, player_pos);
+    log.add(format!("You drop the {}.", name));
+}
+
+/// Opens the inventory screen and, while it is open, takes every key press away from
+/// `player_input`. Using, equipping, taking off or dropping an item takes a turn.
+#[system]
+#[read_component(Carried)]
+#[read_component(Equipped)]
+#[read_component(Item)]
+#[read_component(Name)]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(ProvidesDungeonMap)]
+#[read_component(ProvidesEquipment)]
+#[read_component(ProvidesHealing)]
+#[read_component(Resting)]
+pub fn inventory_input(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] action: &mut Option<Action>,
+    #[resource] targeting: &Targeting,
+    #[resource] inventory: &mut InventoryScreen,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] log: &mut GameLog,
+) {
+    let (player, player_pos, resting) = <(Entity, &Point, Option<&Resting>)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos, resting)| (*entity, *pos, resting.is_some()))
+        .next()
+        .unwrap();
+    let items = inventory_items(ecs, player);
+
+    let InventoryScreen::Open { selected } = *inventory else {
+        if *action == Some(Action::Inventory) && *targeting == Targeting::Inactive && !resting {
+            action.take();
+            if items.is_empty() {
+                log.add("You are not carrying anything.");
+            } else {
+                *inventory = InventoryScreen::Open { selected: 0 };
+            }
+        }
+        return;
+    };
+    let Some(pressed) = action.take() else {
+        return;
+    };
+    if items.is_empty() {
+        *inventory = InventoryScreen::Closed;
+        return;
+    }
+    let selected = selected.min(items.len() - 1);
+
+    let turn_taken = match pressed {
+        Action::Move(Direction::North) => {
+            *inventory = InventoryScreen::Open {
+                selected: selected.saturating_sub(1),
+            };
+            false
+        }
+        Action::Move(Direction::South) => {
+            *inventory = InventoryScreen::Open {
+                selected: (selected + 1).min(items.len() - 1),
+            };
+            false
+        }
+        Action::Inventory | Action::Cancel => {
+            *inventory = InventoryScreen::Closed;
+            false
+        }
+        Action::Confirm => use_selected(ecs, commands, player, &items[selected], log),
+        Action::Drop => {
+            drop_selected(commands, player_pos, &items[selected], log);
+            if items.len() == 1 {
+                *inventory = InventoryScreen::Closed;
+            }
+            true
+        }
+        _ => false,
+    };
+    if turn_taken {
+        *turn_state = TurnState::PlayerTurn;
+    }
+}
@@ -1,6 +1,7 @@
 use crate::prelude::*;
 
 #[system]
+#[allow(clippy::borrowed_box)]
 #[read_component(FieldOfView)]
 #[read_component(Point)]
 pub fn map_render(
@@ -20,9 +21,9 @@ pub fn map_render(
         for x in camera.left_x..camera.right_x {
             let point = Point::new(x, y);
             let offset = Point::new(camera.left_x, camera.top_y);
-            if Map::in_bounds(point) {
+            if map.in_bounds(point) {
                 let tile_visible = player_fov.visible_tiles.contains(&point);
-                let idx = Map::map_idx(point);
+                let idx = map.map_idx(point);
                 if tile_visible
                     || map.revealed_tiles[idx] == Revealed::Seen
                     || map.revealed_tiles[idx] == Revealed::FromMap
@@ -1,63 +1,87 @@
 use crate::prelude::*;
+use legion::systems::Builder;
 
+mod close_door;
 mod combat;
 mod end_turn;
 mod entity_renders;
 mod fov;
+mod game_log;
 mod hud;
+mod inventory;
+mod inventory_input;
 mod map_render;
 mod movement;
 mod player_input;
 mod random_move;
+mod ranged_attack;
+mod regeneration;
 mod roaming_and_chasing;
+mod search;
+mod targeting;
 mod tooltips;
 mod use_items;
 
-pub fn build_input_scheduler() -> Schedule {
-    Schedule::builder()
+fn add_render_systems(builder: &mut Builder, rendering: bool) -> &mut Builder {
+    if rendering {
+        builder
+            .add_system(map_render::map_render_system())
+            .add_system(entity_renders::entity_render_system())
+            .add_system(hud::hud_system())
+            .add_system(game_log::game_log_system())
+            .add_system(tooltips::tooltips_system())
+            .add_system(targeting::targeting_system())
+            .add_system(inventory::inventory_system());
+    }
+    builder
+}
+
+pub fn build_input_scheduler(rendering: bool) -> Schedule {
+    let mut builder = Schedule::builder();
+    builder
+        .add_system(inventory_input::inventory_input_system())
         .add_system(player_input::player_input_system())
         .add_system(fov::fov_system())
-        .flush()
-        .add_system(map_render::map_render_system())
-        .add_system(entity_renders::entity_render_system())
-        .add_system(hud::hud_system())
-        .add_system(tooltips::tooltips_system())
-        .build()
+        .flush();
+    add_render_systems(&mut builder, rendering).build()
 }
 
-pub fn build_player_scheduler() -> Schedule {
-    Schedule::builder()
+pub fn build_player_scheduler(rendering: bool) -> Schedule {
+    let mut builder = Schedule::builder();
+    builder
+        .add_system(ranged_attack::ranged_attack_system())
+        .flush()
         .add_system(use_items::use_items_system())
         .add_system(combat::combat_system())
+        .add_system(search::search_system())
+        .add_system(close_door::close_door_system())
         .flush()
         .add_system(movement::movement_system())
         .flush()
         .add_system(fov::fov_system())
         .flush()
-        .add_system(map_render::map_render_system())
-        .add_system(entity_renders::entity_render_system())
-        .add_system(hud::hud_system())
-        .add_system(tooltips::tooltips_system())
+        .add_system(regeneration::regeneration_system());
+    add_render_systems(&mut builder, rendering)
         .add_system(end_turn::end_turn_system())
         .build()
 }
 
-pub fn build_monster_scheduler() -> Schedule {
-    Schedule::builder()
+pub fn build_monster_scheduler(rendering: bool) -> Schedule {
+    let mut builder = Schedule::builder();
+    builder
         .add_system(random_move::random_move_system())
         .add_system(roaming_and_chasing::roaming_and_chasing_system())
         .flush()
+        .add_system(ranged_attack::ranged_attack_system())
+        .flush()
         .add_system(use_items::use_items_system())
         .add_system(combat::combat_system())
         .flush()
         .add_system(movement::movement_system())
         .flush()
         .add_system(fov::fov_system())
-        .flush()
-        .add_system(map_render::map_render_system())
-        .add_system(entity_renders::entity_render_system())
-        .add_system(hud::hud_system())
-        .add_system(tooltips::tooltips_system())
+        .flush();
+    add_render_systems(&mut builder, rendering)
         .add_system(end_turn::end_turn_system())
         .build()
 }
@@ -1,31 +1,298 @@
 use crate::prelude::*;
 
-#[system(for_each)]
+/// Opens or closes the door: every view but those of the `slain`, who are being removed, is
+/// recomputed and every cached Dijkstra map, built while the door was the other way, is dropped.
+pub(super) fn set_door(
+    door: Point,
+    tile: TileType,
+    slain: &[Entity],
+    map: &mut Map,
+    pathfinding: &mut PathfindingCache,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    let idx = map.map_idx(door);
+    map.tiles[idx] = tile;
+    pathfinding.clear();
+    <(Entity, &FieldOfView)>::query()
+        .iter(ecs)
+        .filter(|(entity, _)| !slain.contains(entity))
+        .for_each(|(entity, fov)| commands.add_component(*entity, fov.clone_dirty()));
+}
+
+/// Sets off every trap on the tile the mover just stepped onto and reveals them. Returns whether
+/// a trap killed the mover, which is then being removed.
+#[allow(clippy::too_many_arguments)]
+fn spring_traps(
+    mover: Entity,
+    pos: Point,
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+    camera: &mut Camera,
+    log: &mut GameLog,
+    pathfinding: &mut PathfindingCache,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) -> bool {
+    let traps: Vec<(Entity, TrapEffect, String)> = <(Entity, &Point, &Trap, &Name)>::query()
+        .iter(ecs)
+        .filter(|(_, trap_pos, _, _)| **trap_pos == pos)
+        .map(|(entity, _, trap, name)| (*entity, trap.effect, name.name.clone()))
+        .collect();
+    if traps.is_empty() {
+        return false;
+    }
+
+    let is_player = ecs
+        .entry_ref(mover)
+        .is_ok_and(|entry| entry.get_component::<Player>().is_ok());
+    let seen = is_player
+        || <&FieldOfView>::query()
+            .filter(component::<Player>())
+            .iter(ecs)
+            .any(|fov| fov.visible_tiles.contains(&pos));
+    let mover_name = ecs
+        .entry_ref(mover)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Name>()
+                .ok()
+                .map(|name| name.name.clone())
+        })
+        .unwrap_or_default();
+
+    for (trap, effect, trap_name) in traps {
+        commands.remove_component::<Hidden>(trap);
+        if is_player {
+            log.add(format!("You set off a {}!", trap_name));
+        } else if seen {
+            log.add(format!("The {} sets off a {}.", mover_name, trap_name));
+        }
+
+        match effect {
+            TrapEffect::Damage(damage) => {
+                if let Ok(mut entry) = ecs.entry_mut(mover) {
+                    if let Ok(health) = entry.get_component_mut::<Health>() {
+                        health.current -= damage;
+                        if is_player {
+                            log.add(format!("It hits you for {}.", damage));
+                        }
+                        if health.current < 1 && !is_player {
+                            commands.remove(mover);
+                            if seen {
+                                log.add(format!("The {} dies.", mover_name));
+                            }
+                            return true;
+                        }
+                    }
+                }
+            }
+            TrapEffect::Teleport => {
+                let occupied: Vec<Point> = <&Point>::query()
+                    .filter(component::<Health>())
+                    .iter(ecs)
+                    .copied()
+                    .collect();
+                let reachable = pathfinding.dijkstra_map(map, map.map_idx(pos), true);
+                let targets: Vec<Point> = (0..map.tiles.len())
+                    .filter(|idx| map.tiles[*idx] == TileType::Floor)
+                    .filter(|idx| reachable.map[*idx] < f32::MAX)
+                    .map(|idx| map.index_to_point2d(idx))
+                    .filter(|point| *point != pos && !occupied.contains(point))
+                    .collect();
+                if let Some(target) = rng.random_slice_entry(&targets) {
+                    commands.add_component(mover, *target);
+                    if is_player {
+                        camera.on_player_move(*target);
+                        log.add("The world lurches around you.");
+                    }
+                }
+            }
+            TrapEffect::Alarm(radius) => {
+                if seen {
+                    log.add("An alarm rings out!");
+                }
+                <(&Point, &mut RoamingAndChasingPlayer)>::query()
+                    .iter_mut(ecs)
+                    .filter(|(monster_pos, _)| {
+                        DistanceAlg::Pythagoras.distance2d(**monster_pos, pos) <= radius as f32
+                    })
+                    .for_each(|(_, roaming)| roaming.going_to = Some(pos));
+            }
+        }
+    }
+    false
+}
+
+/// Carries out every move asked for this turn. A mover killed by a trap gets no further
+/// commands this turn, since it is being removed.
+#[system]
+#[allow(clippy::too_many_arguments)]
 #[read_component(FieldOfView)]
+#[read_component(Hidden)]
+#[read_component(OpensDoors)]
 #[read_component(Player)]
+#[read_component(Point)]
+#[read_component(Name)]
+#[read_component(Item)]
+#[read_component(Trap)]
+#[read_component(WantsToMove)]
+#[write_component(Health)]
+#[write_component(RoamingAndChasingPlayer)]
 pub fn movement(
-    entity: &Entity,
-    want_move: &WantsToMove,
     #[resource] map: &mut Map,
     #[resource] camera: &mut Camera,
+    #[resource] log: &mut GameLog,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] pathfinding: &mut PathfindingCache,
+    #[resource] rng: &mut RandomNumberGenerator,
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
 ) {
-    if map.can_enter_tile(want_move.destination) {
-        commands.add_component(want_move.entity, want_move.destination);
+    let moves: Vec<(Entity, WantsToMove)> = <(Entity, &WantsToMove)>::query()
+        .iter(ecs)
+        .map(|(message, want_move)| (*message, *want_move))
+        .collect();
 
-        if let Ok(entry) = ecs.entry_ref(want_move.entity) {
-            if let Ok(fov) = entry.get_component::<FieldOfView>() {
-                commands.add_component(want_move.entity, fov.clone_dirty());
+    let mut slain = Vec::new();
+    for (message, want_move) in moves {
+        commands.remove(message);
+        if slain.contains(&want_move.entity) {
+            continue;
+        }
+        let destination_idx = map.try_idx(want_move.destination);
+        if destination_idx.map(|idx| map.tiles[idx]) == Some(TileType::DoorClosed) {
+            if let Ok(entry) = ecs.entry_ref(want_move.entity) {
+                let is_player = entry.get_component::<Player>().is_ok();
+                if is_player || entry.get_component::<OpensDoors>().is_ok() {
+                    set_door(
+                        want_move.destination,
+                        TileType::DoorOpen,
+                        &slain,
+                        map,
+                        pathfinding,
+                        ecs,
+                        commands,
+                    );
+                    if is_player {
+                        log.add("You open the door.");
+                    }
+                }
+            }
+        } else if map.can_enter_tile(want_move.destination) {
+            commands.add_component(want_move.entity, want_move.destination);
 
-                if entry.get_component::<Player>().is_ok() {
-                    camera.on_player_move(want_move.destination);
-                    fov.visible_tiles.iter().for_each(|pos| {
-                        map.revealed_tiles[Map::map_idx(*pos)] = Revealed::Seen;
-                    })
+            if let Ok(entry) = ecs.entry_ref(want_move.entity) {
+                if let Ok(fov) = entry.get_component::<FieldOfView>() {
+                    commands.add_component(want_move.entity, fov.clone_dirty());
+
+                    if entry.get_component::<Player>().is_ok() {
+                        camera.on_player_move(want_move.destination);
+                        fov.visible_tiles.iter().for_each(|pos| {
+                            let idx = map.map_idx(*pos);
+                            map.revealed_tiles[idx] = Revealed::Seen;
+                        });
+                        <(&Point, &Name)>::query()
+                            .filter(component::<Item>())
+                            .iter(ecs)
+                            .filter(|(pos, _)| **pos == want_move.destination)
+                            .for_each(|(_, name)| {
+                                log.add(format!("You see a {} here.", name.name))
+                            });
+                        match map.tiles[map.map_idx(want_move.destination)] {
+                            TileType::Exit => *turn_state = TurnState::NextLevel,
+                            TileType::UpStairs => *turn_state = TurnState::PreviousLevel,
+                            _ => {}
+                        }
+                    }
                 }
             }
+
+            if spring_traps(
+                want_move.entity,
+                want_move.destination,
+                map,
+                rng,
+                camera,
+                log,
+                pathfinding,
+                ecs,
+                commands,
+            ) {
+                slain.push(want_move.entity);
+            }
         }
     }
-    commands.remove(*entity);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_trap_kill_and_an_opened_door_in_one_turn() {
+        let door = Point::new(4, 1);
+        let mut map = Map::new(7, 3);
+        let idx = map.map_idx(door);
+        map.tiles[idx] = TileType::DoorClosed;
+        let mut resources = Resources::default();
+        resources.insert(Camera::new(Point::new(1, 1), &map));
+        resources.insert(map);
+        resources.insert(GameLog::default());
+        resources.insert(TurnState::MonsterTurn);
+        resources.insert(PathfindingCache::new(PATHFINDING_CACHE_SIZE));
+        resources.insert(RandomNumberGenerator::seeded(1));
+
+        let mut ecs = World::default();
+        let monster = |pos: Point| {
+            (
+                Enemy,
+                pos,
+                Name {
+                    name: "Goblin".to_string(),
+                },
+                Health { current: 1, max: 1 },
+                FieldOfView::new(6),
+            )
+        };
+        let victim = ecs.push(monster(Point::new(1, 1)));
+        let opener = ecs.push(monster(Point::new(5, 1)));
+        ecs.entry(opener).unwrap().add_component(OpensDoors);
+        ecs.push((
+            Point::new(2, 1),
+            Trap {
+                effect: TrapEffect::Damage(5),
+            },
+            Name {
+                name: "Spike Trap".to_string(),
+            },
+            Hidden,
+        ));
+        ecs.push((
+            (),
+            WantsToMove {
+                entity: victim,
+                destination: Point::new(2, 1),
+            },
+        ));
+        ecs.push((
+            (),
+            WantsToMove {
+                entity: opener,
+                destination: door,
+            },
+        ));
+
+        Schedule::builder()
+            .add_system(movement_system())
+            .build()
+            .execute(&mut ecs, &mut resources);
+
+        assert!(ecs.entry(victim).is_none());
+        assert!(resources.get::<Map>().unwrap().tiles[idx] == TileType::DoorOpen);
+        let opener = ecs.entry_ref(opener).unwrap();
+        assert!(opener.get_component::<FieldOfView>().unwrap().is_dirty);
+        assert_eq!(<&WantsToMove>::query().iter(&ecs).count(), 0);
+    }
 }
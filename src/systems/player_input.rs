@@ -1,126 +1,395 @@
+use super::inventory_input::use_selected;
 use crate::prelude::*;
-use std::collections::BTreeMap;
 
-fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point {
+/// Uses the carried item listed with this number and returns whether a turn was taken; an empty
+/// slot costs none. Equipped items have no number, so they are only taken off from the inventory
+/// screen.
+fn use_item(slot: usize, ecs: &SubWorld, commands: &mut CommandBuffer, log: &mut GameLog) -> bool {
     let player_entity = <Entity>::query()
         .filter(component::<Player>())
         .iter(ecs)
         .next()
-        .map(|entity| *entity)
+        .copied()
         .unwrap();
 
-    let mut some_carried_entry: BTreeMap<String, Entity> = BTreeMap::new();
-    <(Entity, &Name, &Carried)>::query()
+    let items = carried_items(ecs, player_entity);
+    match slot.checked_sub(1).and_then(|n| items.get(n)) {
+        Some(item) => use_selected(ecs, commands, player_entity, item, log),
+        None => {
+            log.add(format!("You have no item {}.", slot));
+            false
+        }
+    }
+}
+
+/// Returns whether a turn was taken: an attack on an enemy or a step onto a tile that can be
+/// entered or a door that can be opened. Bumping into anything else costs no turn.
+fn move_player(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &Map,
+    direction: Direction,
+    log: &mut GameLog,
+) -> bool {
+    if direction.is_diagonal() && !map.diagonal_movement {
+        log.add("Diagonal movement is off.");
+        return false;
+    }
+    let (player_entity, destination) = <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos + direction.delta()))
+        .next()
+        .unwrap();
+
+    let victim = <(Entity, &Point)>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .find(|(_, pos)| **pos == destination)
+        .map(|(entity, _)| *entity);
+    if let Some(victim) = victim {
+        commands.push((
+            (),
+            WantsToAttack {
+                attacker: player_entity,
+                victim,
+                weapon: None,
+            },
+        ));
+        return true;
+    }
+
+    let door = map.try_idx(destination).map(|idx| map.tiles[idx]) == Some(TileType::DoorClosed);
+    if !door && !map.can_enter_tile(destination) {
+        log.add("You can't go that way.");
+        return false;
+    }
+    commands.push((
+        (),
+        WantsToMove {
+            entity: player_entity,
+            destination,
+        },
+    ));
+    true
+}
+
+/// Returns whether a turn was taken; there has to be something to pick up.
+fn pick_up(ecs: &SubWorld, commands: &mut CommandBuffer, log: &mut GameLog) -> bool {
+    let (player, player_pos) = <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+        .unwrap();
+    let mut picked_up = false;
+    <(Entity, &Point, &Name)>::query()
         .filter(component::<Item>())
         .iter(ecs)
-        .filter(|(_, _, carried)| carried.by == player_entity)
-        .for_each(|(entity, name, _)| {
-            some_carried_entry.insert(name.name.clone(), *entity);
+        .filter(|(_, &item_pos, _)| item_pos == player_pos)
+        .for_each(|(entity, _, name)| {
+            picked_up = true;
+            commands.remove_component::<Point>(*entity);
+            commands.add_component(*entity, Carried { by: player });
+            log.add(format!("You pick up the {}.", name.name));
         });
+    if !picked_up {
+        log.add("There is nothing here to pick up.");
+    }
+    picked_up
+}
 
-    let item_entity = some_carried_entry.iter().nth(n).map(|(_, entity)| *entity);
+/// Returns whether a turn was taken; there has to be an open door next to the player with
+/// nothing standing in it.
+fn close_doors(ecs: &SubWorld, commands: &mut CommandBuffer, map: &Map, log: &mut GameLog) -> bool {
+    let (player, player_pos) = <(Entity, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+        .unwrap();
+    let doors: Vec<Point> = map
+        .steps(player_pos)
+        .filter(|point| map.tiles[map.map_idx(*point)] == TileType::DoorOpen)
+        .collect();
+    if doors.is_empty() {
+        log.add("There is no open door next to you.");
+        return false;
+    }
+    let free: Vec<Point> = doors
+        .into_iter()
+        .filter(|door| !<&Point>::query().iter(ecs).any(|pos| pos == door))
+        .collect();
+    if free.is_empty() {
+        log.add("Something is in the way of the door.");
+        return false;
+    }
+    for door in free {
+        commands.push((
+            (),
+            WantsToCloseDoor {
+                closer: player,
+                door,
+            },
+        ));
+    }
+    true
+}
+
+/// Returns whether a turn was taken; resting needs missing health and no enemy in view.
+fn start_resting(ecs: &SubWorld, commands: &mut CommandBuffer, log: &mut GameLog) -> bool {
+    let (player, health) = <(Entity, &Health)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    if enemy_in_view(ecs) {
+        log.add("You can't rest with an enemy in view.");
+        false
+    } else if health.current >= health.max {
+        log.add("You are already at full health.");
+        false
+    } else {
+        commands.add_component(*player, Resting);
+        log.add("You rest.");
+        true
+    }
+}
+
+fn start_aiming(
+    ecs: &SubWorld,
+    action: Action,
+    keymap: &Keymap,
+    targeting: &mut Targeting,
+    log: &mut GameLog,
+) {
+    let player_entity = <Entity>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .copied()
+        .unwrap();
 
-    if let Some(item_entity) = item_entity {
+    let weapon = if action == Action::Fire {
+        <(Entity, &Equipped, &Ranged)>::query()
+            .iter(ecs)
+            .find(|(_, equipped, _)| equipped.by == player_entity)
+            .map(|(entity, _, ranged)| (*entity, ranged.range))
+    } else {
+        <(Entity, &Carried, &Ranged)>::query()
+            .filter(component::<ProvidesThrowing>())
+            .iter(ecs)
+            .find(|(_, carried, _)| carried.by == player_entity)
+            .map(|(entity, _, ranged)| (*entity, ranged.range))
+    };
+
+    match weapon {
+        Some((weapon, range)) => {
+            *targeting = Targeting::Aiming { weapon, range };
+            log.add(keymap.aiming_help());
+        }
+        None if action == Action::Fire => log.add("You have no ranged weapon equipped."),
+        None => log.add("You have nothing to throw."),
+    }
+}
+
+/// Returns whether a turn was taken, which only a shot at a valid target does.
+fn aim(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    action: &Option<Action>,
+    clicked_at: Option<Point>,
+    map: &Map,
+    targeting: &mut Targeting,
+    log: &mut GameLog,
+) -> bool {
+    let Targeting::Aiming { weapon, range } = *targeting else {
+        return false;
+    };
+    let (player_entity, player_pos, fov) = <(Entity, &Point, &FieldOfView)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+
+    let target = match (clicked_at, action) {
+        (Some(target), _) => target,
+        (None, Some(Action::Fire)) | (None, Some(Action::Confirm)) => {
+            let nearest = <&Point>::query()
+                .filter(component::<Enemy>())
+                .iter(ecs)
+                .filter(|pos| valid_target(map, fov, *player_pos, **pos, range))
+                .min_by(|a, b| {
+                    let a = DistanceAlg::Pythagoras.distance2d(*player_pos, **a);
+                    let b = DistanceAlg::Pythagoras.distance2d(*player_pos, **b);
+                    a.partial_cmp(&b).unwrap()
+                });
+            match nearest {
+                Some(target) => *target,
+                None => {
+                    log.add("No enemy in range.");
+                    return false;
+                }
+            }
+        }
+        (None, Some(Action::Cancel)) => {
+            *targeting = Targeting::Inactive;
+            return false;
+        }
+        _ => return false,
+    };
+
+    if valid_target(map, fov, *player_pos, target, range) {
         commands.push((
             (),
-            ActivateItem {
-                used_by: player_entity,
-                item: item_entity,
+            WantsToShoot {
+                shooter: *player_entity,
+                target,
+                weapon: Some(weapon),
             },
         ));
+        *targeting = Targeting::Inactive;
+        true
+    } else {
+        log.add("You can't hit that from here.");
+        false
+    }
+}
+
+fn enemy_in_view(ecs: &SubWorld) -> bool {
+    let fov = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    <&Point>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .any(|pos| fov.visible_tiles.contains(pos))
+}
+
+/// Rests another turn, unless the player is healed, sees an enemy or pressed a key. Returns
+/// whether a turn was taken.
+fn keep_resting(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    action: &Option<Action>,
+    player: Entity,
+    health: Health,
+    log: &mut GameLog,
+) -> bool {
+    if action.is_some() {
+        log.add("You stop resting.");
+    } else if enemy_in_view(ecs) {
+        log.add("An enemy comes into view. You stop resting.");
+    } else if health.current >= health.max {
+        log.add("You feel rested.");
+    } else {
+        return true;
+    }
+    commands.remove_component::<Resting>(player);
+    false
+}
+
+/// Carries out an action outside of aiming and resting and returns whether it took a turn.
+/// Actions that cannot be carried out say why and cost no turn.
+fn take_action(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    action: Action,
+    map: &Map,
+    keymap: &Keymap,
+    targeting: &mut Targeting,
+    log: &mut GameLog,
+) -> bool {
+    match action {
+        Action::Move(direction) => move_player(ecs, commands, map, direction, log),
+        Action::Pickup => pick_up(ecs, commands, log),
+        Action::UseSlot(slot) => use_item(slot, ecs, commands, log),
+        Action::Search => {
+            let player = <Entity>::query()
+                .filter(component::<Player>())
+                .iter(ecs)
+                .next()
+                .copied()
+                .unwrap();
+            commands.push(((), WantsToSearch { searcher: player }));
+            true
+        }
+        Action::CloseDoor => close_doors(ecs, commands, map, log),
+        Action::Wait => true,
+        Action::Rest => start_resting(ecs, commands, log),
+        Action::Fire | Action::Throw => {
+            start_aiming(ecs, action, keymap, targeting, log);
+            false
+        }
+        Action::Confirm | Action::Cancel => {
+            log.add("You are not aiming at anything.");
+            false
+        }
+        Action::ScrollLogUp => {
+            log.scroll_up(1);
+            false
+        }
+        Action::ScrollLogDown => {
+            log.scroll_down(1);
+            false
+        }
+        Action::Drop => {
+            log.add("Open the inventory to choose what to drop.");
+            false
+        }
+        Action::Inventory | Action::Save => false,
     }
-    Point::new(0, 0)
 }
 
 #[system]
 #[read_component(Carried)]
+#[read_component(Enemy)]
+#[read_component(Equippable)]
+#[read_component(Equipped)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[read_component(Item)]
 #[read_component(Name)]
 #[read_component(Point)]
-#[write_component(Health)]
+#[read_component(ProvidesDungeonMap)]
+#[read_component(ProvidesEquipment)]
+#[read_component(ProvidesHealing)]
+#[read_component(ProvidesThrowing)]
+#[read_component(Ranged)]
+#[read_component(Resting)]
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
-    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] action: &Option<Action>,
+    #[resource] click: &MouseClick,
+    #[resource] camera: &Camera,
+    #[resource] map: &Map,
+    #[resource] keymap: &Keymap,
+    #[resource] targeting: &mut Targeting,
     #[resource] turn_state: &mut TurnState,
+    #[resource] log: &mut GameLog,
 ) {
-    if let Some(key) = key {
-        let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
-        let delta = match key {
-            VirtualKeyCode::Left => Point::new(-1, 0),
-            VirtualKeyCode::Right => Point::new(1, 0),
-            VirtualKeyCode::Up => Point::new(0, -1),
-            VirtualKeyCode::Down => Point::new(0, 1),
-            VirtualKeyCode::G => {
-                let (player, player_pos) = players
-                    .iter(ecs)
-                    .map(|(entity, pos)| (*entity, *pos))
-                    .next()
-                    .unwrap();
-                <(Entity, &Point)>::query()
-                    .filter(component::<Item>())
-                    .iter(ecs)
-                    .filter(|(_, &item_pos)| item_pos == player_pos)
-                    .for_each(|(entity, _)| {
-                        commands.remove_component::<Point>(*entity);
-                        commands.add_component(*entity, Carried { by: player });
-
-                        let entity_ref = ecs.entry_ref(*entity).unwrap();
-                        if entity_ref.get_component::<Weapon>().is_ok() {
-                            <(Entity, &Carried)>::query()
-                                .filter(component::<Weapon>())
-                                .iter(ecs)
-                                .filter(|(_, c)| c.by == player)
-                                .for_each(|(entity, _)| {
-                                    commands.remove(*entity);
-                                })
-                        }
-                    });
-                Point::new(0, 0)
-            }
-            VirtualKeyCode::Key1 => use_item(0, ecs, commands),
-            VirtualKeyCode::Key2 => use_item(1, ecs, commands),
-            VirtualKeyCode::Key3 => use_item(2, ecs, commands),
-            VirtualKeyCode::Key4 => use_item(3, ecs, commands),
-            VirtualKeyCode::Key5 => use_item(4, ecs, commands),
-            VirtualKeyCode::Key6 => use_item(5, ecs, commands),
-            VirtualKeyCode::Key7 => use_item(6, ecs, commands),
-            VirtualKeyCode::Key8 => use_item(7, ecs, commands),
-            VirtualKeyCode::Key9 => use_item(8, ecs, commands),
-            _ => Point::new(0, 0),
-        };
-
-        let (player_entity, destination) = players
-            .iter(ecs)
-            .map(|(entity, pos)| (*entity, *pos + delta))
-            .next()
-            .unwrap();
-
-        if delta != Point::zero() {
-            let mut hit_something = false;
-            <(Entity, &Point)>::query()
-                .filter(component::<Enemy>())
-                .iter(ecs)
-                .filter(|(_, pos)| **pos == destination)
-                .for_each(|(entity, _)| {
-                    hit_something = true;
-                    commands.push((
-                        (),
-                        WantsToAttack {
-                            attacker: player_entity,
-                            victim: *entity,
-                        },
-                    ));
-                });
-            if !hit_something {
-                commands.push((
-                    (),
-                    WantsToMove {
-                        entity: player_entity,
-                        destination,
-                    },
-                ));
-            }
-        }
+    let resting = <(Entity, &Health)>::query()
+        .filter(component::<Player>() & component::<Resting>())
+        .iter(ecs)
+        .next()
+        .map(|(player, health)| (*player, *health));
+    let turn_taken = if let Some((player, health)) = resting {
+        keep_resting(ecs, commands, action, player, health, log)
+    } else if *targeting != Targeting::Inactive {
+        let clicked_at = click
+            .0
+            .map(|pos| pos + Point::new(camera.left_x, camera.top_y));
+        aim(ecs, commands, action, clicked_at, map, targeting, log)
+    } else if let Some(action) = action {
+        take_action(ecs, commands, *action, map, keymap, targeting, log)
+    } else {
+        false
+    };
+    if turn_taken {
         *turn_state = TurnState::PlayerTurn;
     }
 }
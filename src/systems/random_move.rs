@@ -3,18 +3,18 @@ use crate::prelude::*;
 #[system]
 #[read_component(Point)]
 #[read_component(Player)]
-pub fn random_move(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+pub fn random_move(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] map: &Map,
+) {
     <(Entity, &Point)>::query()
         .filter(component::<MovingRandomly>())
         .iter(ecs)
         .for_each(|(entity, pos)| {
-            let mut rng = RandomNumberGenerator::new();
-            let destination = match rng.range(0, 4) {
-                0 => Point::new(-1, 0),
-                1 => Point::new(1, 0),
-                2 => Point::new(0, -1),
-                _ => Point::new(0, 1),
-            } + *pos;
+            let offsets = map.step_offsets();
+            let destination = offsets[rng.range(0, offsets.len())] + *pos;
 
             let mut attacked = false;
             <(Entity, &Point)>::query()
@@ -32,6 +32,7 @@ pub fn random_move(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
                             WantsToAttack {
                                 attacker: *entity,
                                 victim: *victim,
+                                weapon: None,
                             },
                         ));
                     }
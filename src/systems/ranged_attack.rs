@@ -0,0 +1,115 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(WantsToShoot)]
+#[read_component(Point)]
+#[read_component(Health)]
+#[read_component(Player)]
+#[read_component(Ranged)]
+#[read_component(ProvidesThrowing)]
+#[read_component(Name)]
+pub fn ranged_attack(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &Map,
+    #[resource] log: &mut GameLog,
+) {
+    <(Entity, &WantsToShoot)>::query()
+        .iter(ecs)
+        .for_each(|(message, shoot)| {
+            commands.remove(*message);
+
+            let shooter = ecs.entry_ref(shoot.shooter).unwrap();
+            let shooter_pos = *shooter.get_component::<Point>().unwrap();
+            let is_player = shooter.get_component::<Player>().is_ok();
+            let shooter_name = shooter
+                .get_component::<Name>()
+                .map(|name| name.name.clone())
+                .unwrap_or_default();
+            let ranged = shoot.weapon.unwrap_or(shoot.shooter);
+            let ranged = ecs.entry_ref(ranged).unwrap();
+            let range = ranged.get_component::<Ranged>().map_or(0, |r| r.range);
+            let weapon_name = ranged
+                .get_component::<Name>()
+                .map(|name| name.name.clone())
+                .unwrap_or_default();
+
+            if let Some(weapon) = shoot.weapon {
+                if ranged.get_component::<ProvidesThrowing>().is_ok() {
+                    commands.remove_component::<Carried>(weapon);
+                    commands.add_component(weapon, landing_tile(map, shooter_pos, shoot.target));
+                    log.add(format!("You throw the {}.", weapon_name));
+                }
+            } else if !is_player {
+                log.add(format!("{} shoots at you.", shooter_name));
+            }
+
+            let in_range = DistanceAlg::Pythagoras.distance2d(shooter_pos, shoot.target)
+                <= range as f32
+                && map.line_of_sight(shooter_pos, shoot.target);
+            let victim = <(Entity, &Point)>::query()
+                .filter(component::<Health>())
+                .iter(ecs)
+                .find(|(_, pos)| **pos == shoot.target)
+                .map(|(entity, _)| *entity);
+
+            match (in_range, victim) {
+                (true, Some(victim)) => {
+                    commands.push((
+                        (),
+                        WantsToAttack {
+                            attacker: shoot.shooter,
+                            victim,
+                            weapon: shoot.weapon,
+                        },
+                    ));
+                }
+                _ if is_player => log.add("You miss."),
+                _ => log.add(format!("{} misses.", shooter_name)),
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_throw_at_a_wall_lands_in_front_of_it() {
+        let wall = Point::new(5, 1);
+        let mut map = Map::new(7, 3);
+        let idx = map.map_idx(wall);
+        map.tiles[idx] = TileType::Wall;
+        let mut resources = Resources::default();
+        resources.insert(map);
+        resources.insert(GameLog::default());
+
+        let mut ecs = World::default();
+        let player = ecs.push((Player { map_level: 0 }, Point::new(1, 1)));
+        let dagger = ecs.push((
+            Item,
+            Name {
+                name: "Dagger".to_string(),
+            },
+            ProvidesThrowing,
+            Ranged { range: 6 },
+            Carried { by: player },
+        ));
+        ecs.push((
+            (),
+            WantsToShoot {
+                shooter: player,
+                target: wall,
+                weapon: Some(dagger),
+            },
+        ));
+        Schedule::builder()
+            .add_system(ranged_attack_system())
+            .build()
+            .execute(&mut ecs, &mut resources);
+
+        let dagger = ecs.entry_ref(dagger).unwrap();
+        assert_eq!(dagger.get_component::<Point>(), Ok(&Point::new(4, 1)));
+        assert!(dagger.get_component::<Carried>().is_err());
+    }
+}
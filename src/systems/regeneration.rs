@@ -0,0 +1,34 @@
+use crate::prelude::*;
+
+const REGENERATION_TURNS: i32 = 10;
+
+/// Heals one hit point for every `REGENERATION_TURNS` turns in a row that pass without an enemy
+/// in view. Seeing an enemy starts the count over.
+#[system]
+#[read_component(Enemy)]
+#[read_component(FieldOfView)]
+#[read_component(Point)]
+#[write_component(Health)]
+#[write_component(Regeneration)]
+pub fn regeneration(ecs: &mut SubWorld) {
+    let enemies: Vec<Point> = <&Point>::query()
+        .filter(component::<Enemy>())
+        .iter(ecs)
+        .copied()
+        .collect();
+
+    <(&FieldOfView, &mut Health, &mut Regeneration)>::query()
+        .iter_mut(ecs)
+        .for_each(|(fov, health, regeneration)| {
+            let enemy_in_view = enemies.iter().any(|pos| fov.visible_tiles.contains(pos));
+            if enemy_in_view || health.current < 1 || health.current >= health.max {
+                regeneration.turns = 0;
+                return;
+            }
+            regeneration.turns += 1;
+            if regeneration.turns >= REGENERATION_TURNS {
+                regeneration.turns = 0;
+                health.current += 1;
+            }
+        });
+}
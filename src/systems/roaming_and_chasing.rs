@@ -26,16 +26,18 @@ fn sample_lowest_exit(
 
 #[system]
 #[read_component(FieldOfView)]
+#[read_component(OpensDoors)]
 #[read_component(Point)]
 #[read_component(Player)]
+#[read_component(Ranged)]
 #[write_component(RoamingAndChasingPlayer)]
 pub fn roaming_and_chasing(
     #[resource] map: &Map,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] pathfinding: &mut PathfindingCache,
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
 ) {
-    let mut rng = RandomNumberGenerator::new();
-
     let player_pos = *<(&Point, &Player)>::query().iter(ecs).next().unwrap().0;
 
     <(&Point, &FieldOfView, &mut RoamingAndChasingPlayer)>::query()
@@ -50,38 +52,70 @@ pub fn roaming_and_chasing(
                 roaming_and_chasing_player.going_to = match rng.range(1, 50) {
                     1 => Some(roaming_and_chasing_player.home_location),
                     2..5 => {
-                        let idx = Map::map_idx(*pos);
-                        let dijkstra_map = &map.dijkstra_maps[idx];
+                        let idx = map.map_idx(*pos);
+                        let dijkstra_map =
+                            DijkstraMap::new(map.width, map.height, &[idx], map, 10.0);
                         let targets = dijkstra_map
-                            .as_ref()
-                            .unwrap()
                             .map
                             .iter()
                             .enumerate()
                             .filter(|(_, dist)| **dist < 10.0)
                             .map(|(idx, _)| idx)
                             .collect::<Vec<usize>>();
-                        let target_idx = targets[rng.random_slice_index(&targets).unwrap()];
-                        Some(Map::map_point(target_idx))
+                        rng.random_slice_entry(&targets)
+                            .map(|target_idx| map.map_point(*target_idx))
                     }
                     _ => None,
                 };
             }
         });
 
-    <(Entity, &Point, &RoamingAndChasingPlayer)>::query()
+    <(Entity, &Point, &FieldOfView, &RoamingAndChasingPlayer)>::query()
         .iter(ecs)
-        .filter(|(_, _, roaming_and_chasing_player)| roaming_and_chasing_player.going_to.is_some())
-        .for_each(|(entity, pos, roaming_and_chasing_player)| {
+        .filter(|(_, _, _, roaming_and_chasing_player)| {
+            roaming_and_chasing_player.going_to.is_some()
+        })
+        .for_each(|(entity, pos, fov, roaming_and_chasing_player)| {
+            let shooting_range = ecs
+                .entry_ref(*entity)
+                .unwrap()
+                .get_component::<Ranged>()
+                .map(|ranged| ranged.range)
+                .ok();
+            if let Some(range) = shooting_range {
+                if !map.is_adjacent(*pos, player_pos)
+                    && valid_target(map, fov, *pos, player_pos, range)
+                {
+                    commands.push((
+                        (),
+                        WantsToShoot {
+                            shooter: *entity,
+                            target: player_pos,
+                            weapon: None,
+                        },
+                    ));
+                    return;
+                }
+            }
             if let Some(going_to) = roaming_and_chasing_player.going_to {
-                let idx = Map::map_idx(*pos);
-                let dijkstra_map = map.dijkstra_maps[Map::map_idx(going_to)].as_ref().unwrap();
-                if let Some(destination) = sample_lowest_exit(&mut rng, dijkstra_map, idx, map) {
-                    let distance = DistanceAlg::Pythagoras.distance2d(*pos, going_to);
-                    let destination = if distance > 1.2 {
-                        map.index_to_point2d(destination)
-                    } else {
+                let idx = map.map_idx(*pos);
+                let opens_doors = ecs
+                    .entry_ref(*entity)
+                    .unwrap()
+                    .get_component::<OpensDoors>()
+                    .is_ok();
+                let dijkstra_map =
+                    pathfinding.dijkstra_map(map, map.map_idx(going_to), opens_doors);
+                let destination = if opens_doors {
+                    sample_lowest_exit(rng, dijkstra_map, idx, &map.for_door_openers())
+                } else {
+                    sample_lowest_exit(rng, dijkstra_map, idx, map)
+                };
+                if let Some(destination) = destination {
+                    let destination = if map.is_adjacent(*pos, going_to) {
                         going_to
+                    } else {
+                        map.index_to_point2d(destination)
                     };
 
                     let mut attacked = false;
@@ -101,6 +135,7 @@ pub fn roaming_and_chasing(
                                     WantsToAttack {
                                         attacker: *entity,
                                         victim: *victim,
+                                        weapon: None,
                                     },
                                 ));
                                 attacked = true;
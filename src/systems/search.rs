@@ -0,0 +1,59 @@
+use crate::prelude::*;
+
+const SEARCH_RADIUS: f32 = 3.0;
+/// Percent chance to find each hidden trap in range with one search.
+const SEARCH_CHANCE: i32 = 60;
+
+/// Reveals the hidden traps a searcher can see close by, each one on a perception roll.
+#[system]
+#[read_component(FieldOfView)]
+#[read_component(Hidden)]
+#[read_component(Name)]
+#[read_component(Player)]
+#[read_component(Point)]
+#[read_component(Trap)]
+#[read_component(WantsToSearch)]
+pub fn search(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] log: &mut GameLog,
+) {
+    let searches: Vec<(Entity, Entity)> = <(Entity, &WantsToSearch)>::query()
+        .iter(ecs)
+        .map(|(message, search)| (*message, search.searcher))
+        .collect();
+
+    for (message, searcher) in searches {
+        commands.remove(message);
+        let Ok(entry) = ecs.entry_ref(searcher) else {
+            continue;
+        };
+        let (Ok(searcher_pos), Ok(fov)) = (
+            entry.get_component::<Point>(),
+            entry.get_component::<FieldOfView>(),
+        ) else {
+            continue;
+        };
+        let is_player = entry.get_component::<Player>().is_ok();
+        if is_player {
+            log.add("You search the area.");
+        }
+
+        <(Entity, &Point, &Name)>::query()
+            .filter(component::<Trap>() & component::<Hidden>())
+            .iter(ecs)
+            .filter(|(_, pos, _)| {
+                fov.visible_tiles.contains(pos)
+                    && DistanceAlg::Pythagoras.distance2d(*searcher_pos, **pos) <= SEARCH_RADIUS
+            })
+            .for_each(|(trap, _, name)| {
+                if rng.range(0, 100) < SEARCH_CHANCE {
+                    commands.remove_component::<Hidden>(*trap);
+                    if is_player {
+                        log.add(format!("You find a {}!", name.name));
+                    }
+                }
+            });
+    }
+}
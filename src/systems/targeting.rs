@@ -0,0 +1,46 @@
+use crate::prelude::*;
+
+#[system]
+#[read_component(FieldOfView)]
+#[read_component(Point)]
+pub fn targeting(
+    ecs: &SubWorld,
+    #[resource] targeting: &Targeting,
+    #[resource] mouse_pos: &Point,
+    #[resource] camera: &Camera,
+    #[resource] map: &Map,
+) {
+    let Targeting::Aiming { range, .. } = *targeting else {
+        return;
+    };
+    let (player_fov, player_pos) = <(&FieldOfView, &Point)>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let target = *mouse_pos + offset;
+    let color = if valid_target(map, player_fov, *player_pos, target, range) {
+        ColorPair::new(GREEN, BLACK)
+    } else {
+        ColorPair::new(RED, BLACK)
+    };
+    line2d_bresenham(*player_pos, target)
+        .iter()
+        .skip(1)
+        .for_each(|pos| {
+            let glyph = if *pos == target { 'X' } else { '*' };
+            draw_batch.set(
+                (*pos - offset) * 4 + Point::new(1, 1),
+                color,
+                to_cp437(glyph),
+            );
+        });
+    draw_batch.print_color_centered(1, "Targeting", ColorPair::new(YELLOW, BLACK));
+
+    draw_batch.submit(10200).expect("Batch error");
+}
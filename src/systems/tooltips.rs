@@ -2,6 +2,7 @@ use crate::prelude::*;
 
 #[system]
 #[read_component(FieldOfView)]
+#[read_component(Hidden)]
 #[read_component(Point)]
 #[read_component(Name)]
 #[read_component(Health)]
@@ -19,6 +20,7 @@ pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camer
     draw_batch.target(2);
 
     <(Entity, &Point, &Name)>::query()
+        .filter(!component::<Hidden>())
         .iter(ecs)
         .filter(|(_, pos, _)| **pos == map_pos && player_fov.visible_tiles.contains(pos))
         .for_each(|(entity, _, name)| {
@@ -8,23 +8,38 @@ use std::collections::HashSet;
 #[read_component(ProvidesEquipment)]
 #[read_component(ProvidesHealing)]
 #[read_component(Carried)]
-#[read_component(Weapon)]
+#[read_component(Equippable)]
 #[write_component(Health)]
 #[read_component(Equipped)]
-pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+#[read_component(Name)]
+pub fn use_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] log: &mut GameLog,
+) {
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
-    let mut unequip_weapons_by = HashSet::new();
+    let mut unequip_slots_by = HashSet::new();
 
     <(Entity, &ActivateItem)>::query()
         .iter(ecs)
         .for_each(|(entity, activate)| {
             let item = ecs.entry_ref(activate.item).unwrap();
             let item_depletes = item.get_component::<ProvidesDepletion>().is_ok();
+            let item_name = item
+                .get_component::<Name>()
+                .map(|name| name.name.clone())
+                .unwrap_or_default();
 
             if let Ok(healing) = item.get_component::<ProvidesHealing>() {
                 healing_to_apply.push((activate.used_by, healing.amount));
+                log.add(format!("You drink a {} (+{}).", item_name, healing.amount));
             }
             if item.get_component::<ProvidesDungeonMap>().is_ok() {
+                log.add(format!(
+                    "You read the {}. The level is revealed.",
+                    item_name
+                ));
                 map.revealed_tiles.iter_mut().for_each(|t| {
                     if *t == Revealed::Unrevealed {
                         *t = Revealed::FromMap
@@ -33,9 +48,10 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
             }
 
             if item.get_component::<ProvidesEquipment>().is_ok() {
-                if item.get_component::<Weapon>().is_ok() {
-                    unequip_weapons_by.insert(activate.used_by);
+                if let Ok(equippable) = item.get_component::<Equippable>() {
+                    unequip_slots_by.insert((activate.used_by, equippable.slot));
                 }
+                log.add(format!("You equip the {}.", item_name));
 
                 commands.remove_component::<Carried>(activate.item);
                 commands.add_component(
@@ -60,12 +76,13 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
         }
     }
 
-    if !unequip_weapons_by.is_empty() {
-        <(Entity, &Equipped)>::query()
-            .filter(component::<Weapon>())
+    if !unequip_slots_by.is_empty() {
+        <(Entity, &Equipped, &Equippable)>::query()
             .iter(ecs)
-            .filter(|(_, equipped)| unequip_weapons_by.contains(&equipped.by))
-            .for_each(|(entity, equipped)| {
+            .filter(|(_, equipped, equippable)| {
+                unequip_slots_by.contains(&(equipped.by, equippable.slot))
+            })
+            .for_each(|(entity, equipped, _)| {
                 let by = equipped.by;
                 commands.remove_component::<Equipped>(*entity);
                 commands.add_component(*entity, Carried { by });
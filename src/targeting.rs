@@ -0,0 +1,30 @@
+use crate::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Targeting {
+    Inactive,
+    Aiming { weapon: Entity, range: i32 },
+}
+
+/// Where on the screen the mouse was clicked this frame, if it was.
+pub struct MouseClick(pub Option<Point>);
+
+pub fn valid_target(map: &Map, fov: &FieldOfView, from: Point, to: Point, range: i32) -> bool {
+    from != to
+        && fov.visible_tiles.contains(&to)
+        && DistanceAlg::Pythagoras.distance2d(from, to) <= range as f32
+        && map.line_of_sight(from, to)
+}
+
+/// Where something thrown from `from` at `to` comes to rest: on the target, or on the last tile
+/// short of it that can be entered if the target is a wall or deep water.
+pub fn landing_tile(map: &Map, from: Point, to: Point) -> Point {
+    if map.can_enter_tile(to) {
+        return to;
+    }
+    line2d_bresenham(from, to)
+        .into_iter()
+        .rev()
+        .find(|point| map.can_enter_tile(*point))
+        .unwrap_or(from)
+}
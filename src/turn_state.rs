@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TurnState {
     AwaitingInput,
     PlayerTurn,
@@ -6,4 +8,5 @@ pub enum TurnState {
     GameOver,
     Victory,
     NextLevel,
+    PreviousLevel,
 }